@@ -11,3 +11,51 @@ pub const SOL_VAULT_SEED: &[u8] = b"sol_vault";
 
 #[constant]
 pub const USDC_VAULT_SEED: &[u8] = b"usdc_vault";
+
+#[constant]
+pub const PAYMENT_MINT_SEED: &[u8] = b"payment_mint";
+
+#[constant]
+pub const PAYMENT_VAULT_SEED: &[u8] = b"payment_vault";
+
+#[constant]
+pub const EXCHANGE_RATE_SEED: &[u8] = b"exchange_rate";
+
+#[constant]
+pub const DUTCH_AUCTION_SEED: &[u8] = b"dutch_auction";
+
+#[constant]
+pub const AUCTION_BID_SEED: &[u8] = b"auction_bid";
+
+#[constant]
+pub const FAIR_LAUNCH_SEED: &[u8] = b"fair_launch";
+
+#[constant]
+pub const FAIR_LAUNCH_DEPOSIT_SEED: &[u8] = b"fair_launch_deposit";
+
+#[constant]
+pub const LOTTERY_SEED: &[u8] = b"lottery";
+
+#[constant]
+pub const LOTTERY_TICKET_SEED: &[u8] = b"lottery_ticket";
+
+#[constant]
+pub const REFERRER_SEED: &[u8] = b"referrer";
+
+#[constant]
+pub const PROMO_CODE_SEED: &[u8] = b"promo_code";
+
+#[constant]
+pub const OTC_GRANT_SEED: &[u8] = b"otc_grant";
+
+#[constant]
+pub const VESTING_GRANT_SEED: &[u8] = b"vesting_grant";
+
+#[constant]
+pub const MERKLE_DISTRIBUTOR_SEED: &[u8] = b"merkle_distributor";
+
+#[constant]
+pub const MERKLE_CLAIM_SEED: &[u8] = b"merkle_claim";
+
+#[constant]
+pub const PURCHASE_RECEIPT_SEED: &[u8] = b"purchase_receipt";
@@ -52,4 +52,100 @@ pub enum PresaleError {
     
     #[msg("Invalid vesting schedule")]
     InvalidVestingSchedule,
+    
+    #[msg("Payment exceeds the maximum the buyer agreed to")]
+    SlippageExceeded,
+    
+    #[msg("Active phase differs from the expected phase")]
+    UnexpectedPhase,
+    
+    #[msg("Transaction deadline has passed")]
+    DeadlineExceeded,
+    
+    #[msg("Purchase yields fewer tokens than the buyer's minimum")]
+    MinTokensNotMet,
+    
+    #[msg("Mint has a freeze authority")]
+    FreezeAuthorityNotAllowed,
+    
+    #[msg("Payment vault does not match the payment mint")]
+    InvalidPaymentVault,
+    
+    #[msg("Price feed account is invalid")]
+    InvalidPriceFeed,
+    
+    #[msg("Price feed is stale")]
+    StalePriceFeed,
+    
+    #[msg("Price feed confidence interval is too wide")]
+    PriceConfidenceTooWide,
+    
+    #[msg("Exchange rate is invalid")]
+    InvalidExchangeRate,
+    
+    #[msg("Exchange rate change exceeds the maximum deviation")]
+    RateDeviationTooLarge,
+    
+    #[msg("Exchange rate was updated too recently")]
+    RateUpdateTooSoon,
+    
+    #[msg("Auction is not accepting bids")]
+    AuctionNotActive,
+    
+    #[msg("Auction has not ended yet")]
+    AuctionStillRunning,
+    
+    #[msg("Auction has not been settled")]
+    AuctionNotSettled,
+    
+    #[msg("Deposit window is not open")]
+    DepositWindowClosed,
+    
+    #[msg("Deposit window has not closed yet")]
+    DepositWindowOpen,
+    
+    #[msg("Round has not been settled")]
+    RoundNotSettled,
+    
+    #[msg("Round has already been settled")]
+    RoundAlreadySettled,
+    
+    #[msg("Revealed seed does not match the commitment")]
+    InvalidSeedReveal,
+    
+    #[msg("Seed can still be revealed")]
+    RevealWindowOpen,
+    #[msg("Referrer is not registered or inactive")]
+    InvalidReferrer,
+    
+    #[msg("No referral rewards to claim")]
+    NoReferralRewards,
+    
+    #[msg("Referral commissions are only paid on SOL and USDC purchases")]
+    CommissionNotSupported,
+    
+    #[msg("Treasury does not hold every token still owed")]
+    TreasuryUnderfunded,
+    #[msg("Promo code is invalid or not valid in this phase")]
+    InvalidPromoCode,
+    
+    #[msg("Promo code has expired")]
+    PromoCodeExpired,
+    
+    #[msg("Promo code usage limit reached")]
+    PromoCodeExhausted,
+    
+    #[msg("Grant exceeds the reserved allocation")]
+    ExceedsReservedAllocation,
+    
+    #[msg("Grant has already been revoked")]
+    GrantRevoked,
+    
+    #[msg("Tokens have already launched")]
+    AlreadyLaunched,
+    
+    #[msg("Grant is not revocable")]
+    GrantNotRevocable,
+    #[msg("Invalid Merkle proof")]
+    InvalidMerkleProof,
 }
@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+use crate::state::{DiscountTier, PaymentType, ReferralReward, PHASE_COUNT};
+
+// Typed events for indexers, emitted alongside the human-readable `msg!` logs.
+
+#[event]
+pub struct PurchaseEvent {
+    pub buyer: Pubkey,
+    pub round: SaleRound,
+    pub phase: u8, // phase the order started in, zero outside the phases
+    pub phases_filled: u8,
+    pub token_amount: u64,
+    pub payment_amount: u64, // what arrived in the vault
+    pub payment_type: PaymentType,
+    pub payment_mint: Pubkey,
+    pub price: u64, // spot price of the first fill, per whole sale token
+    pub discount_bps: u16,
+    pub bonus_tokens: u64,
+    pub referrer: Option<Pubkey>,
+}
+
+#[event]
+pub struct ClaimEvent {
+    pub wallet: Pubkey,
+    pub source: ClaimSource,
+    pub amount: u64, // sale tokens transferred, or added to the vesting schedule
+    pub received_amount: u64, // after Token-2022 transfer fees, zero when nothing was transferred
+    pub sol_paid: u64, // deposit refunds, or commissions for referral claims
+    pub usdc_paid: u64,
+}
+
+#[event]
+pub struct DepositEvent {
+    pub wallet: Pubkey,
+    pub round: SaleRound,
+    pub payment_type: PaymentType,
+    pub amount: u64, // what arrived in the vault
+    pub demand: u64, // fair-launch tokens demanded at a full fill, zero for lottery tickets
+}
+
+#[event]
+pub struct GrantEvent {
+    pub authority: Pubkey,
+    pub beneficiary: Pubkey,
+    pub kind: GrantKind,
+    pub amount: u64, // tokens granted, or taken back when revoked
+    pub is_revoked: bool,
+}
+
+#[event]
+pub struct PauseEvent {
+    pub authority: Pubkey,
+    pub is_paused: bool,
+}
+
+#[event]
+pub struct ConfigUpdatedEvent {
+    pub updated_by: Pubkey,
+    pub section: ConfigSection, // carries the settings as they stand after the update
+}
+
+#[event]
+pub struct BurnEvent {
+    pub authority: Pubkey,
+    pub amount: u64,
+}
+
+/// Sale round a purchase or deposit went into.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SaleRound {
+    Phases,
+    DutchAuction,
+    FairLaunch,
+    Lottery,
+}
+
+/// Instruction a `ClaimEvent` comes from.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ClaimSource {
+    Vested,
+    VestingGrant,
+    DutchAuction,
+    FairLaunch,
+    Lottery,
+    Referral,
+    MerkleAllocation,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum GrantKind {
+    /// `grant_allocation`
+    Allocation,
+    /// `create_vesting_grant`
+    VestingGrant,
+}
+
+/// Which group of settings a `ConfigUpdatedEvent` refers to, with its values.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum ConfigSection {
+    /// `update_config` limits and phase rules
+    Limits {
+        max_purchase_per_transaction: u64,
+        max_purchase_per_wallet: u64,
+        min_time_between_purchases: i64,
+        auto_rollover: bool,
+        carry_over_unsold: bool,
+        trim_to_limits: bool,
+    },
+    DiscountTiers {
+        tiers: Vec<DiscountTier>,
+    },
+    Referral {
+        reward: ReferralReward,
+        reward_bps: u16,
+        referral_pool: u64,
+    },
+    BonusPool {
+        bonus_pool: u64,
+        vesting_launch_percentage: u8,
+        vesting_monthly_percentage: u8,
+    },
+    ReservedAllocation {
+        reserved_allocation: u64,
+    },
+    SolPriceFeed {
+        price_feed: Option<Pubkey>,
+        max_price_age: i64,
+        max_confidence_bps: u16,
+    },
+    ExchangeRate {
+        rate_updater: Pubkey,
+        rate: u64,
+        max_deviation_bps: u16,
+        max_age: i64,
+        min_update_interval: i64,
+        use_exchange_rate: bool,
+    },
+    PaymentMint {
+        mint: Pubkey,
+        is_enabled: bool,
+        usd_pegged: bool,
+        prices: [u64; PHASE_COUNT],
+    },
+    PromoCode {
+        code_hash: [u8; 32],
+        discount_bps: u16,
+        max_uses: u32,
+        expires_at: i64,
+        phase: Option<u8>,
+        is_active: bool,
+    },
+}
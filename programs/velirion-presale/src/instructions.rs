@@ -5,6 +5,17 @@ pub mod pause;
 pub mod burn_unsold;
 pub mod get_status;
 pub mod update_config;
+pub mod payment_mint;
+pub mod price_feed;
+pub mod exchange_rate;
+pub mod dutch_auction;
+pub mod fair_launch;
+pub mod lottery;
+pub mod referral;
+pub mod promo_code;
+pub mod otc_grant;
+pub mod vesting_grant;
+pub mod merkle_distributor;
 
 pub use initialize::*;
 pub use purchase::*;
@@ -13,3 +24,14 @@ pub use pause::*;
 pub use burn_unsold::*;
 pub use get_status::*;
 pub use update_config::*;
+pub use payment_mint::*;
+pub use price_feed::*;
+pub use exchange_rate::*;
+pub use dutch_auction::*;
+pub use fair_launch::*;
+pub use lottery::*;
+pub use referral::*;
+pub use promo_code::*;
+pub use otc_grant::*;
+pub use vesting_grant::*;
+pub use merkle_distributor::*;
@@ -1,8 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount, Mint, Burn};
+use anchor_spl::token_interface::{Burn, Mint, TokenAccount, TokenInterface};
 use crate::state::*;
 use crate::constants::*;
 use crate::error::PresaleError;
+use crate::events::BurnEvent;
 
 #[derive(Accounts)]
 pub struct BurnUnsold<'info> {
@@ -10,13 +11,19 @@ pub struct BurnUnsold<'info> {
     pub authority: Signer<'info>,
     
     #[account(
+        mut,
         seeds = [PRESALE_CONFIG_SEED],
         bump = presale_config.bump,
         constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
     )]
     pub presale_config: Account<'info, PresaleConfig>,
     
-    pub token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        address = presale_config.token_mint @ PresaleError::InvalidTokenMint,
+        mint::token_program = token_program
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
     
     /// CHECK: Treasury account that holds the unsold tokens
     #[account(
@@ -24,9 +31,9 @@ pub struct BurnUnsold<'info> {
         constraint = treasury.key() == presale_config.treasury @ PresaleError::InvalidTreasury,
         constraint = treasury.mint == token_mint.key() @ PresaleError::InvalidTokenMint
     )]
-    pub treasury: Account<'info, TokenAccount>,
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 pub fn handler(ctx: Context<BurnUnsold>) -> Result<()> {
@@ -40,19 +47,19 @@ pub fn handler(ctx: Context<BurnUnsold>) -> Result<()> {
         PresaleError::PresaleNotEnded
     );
     
+    // Auction and fair-launch allocations stay until their round settles
+    require!(
+        presale_config.round_tokens_reserved == 0,
+        PresaleError::RoundNotSettled
+    );
+    
     // Calculate unsold tokens
-    let unsold_tokens = presale_config
-        .total_tokens_for_sale
-        .checked_sub(presale_config.tokens_sold)
-        .ok_or(PresaleError::MathOverflow)?;
+    let unsold_tokens = presale_config.unsold_supply();
     
     require!(unsold_tokens > 0, PresaleError::NoTokensToClaim);
     
-    // Check treasury has enough tokens
-    require!(
-        ctx.accounts.treasury.amount >= unsold_tokens,
-        PresaleError::InsufficientTokensInPhase
-    );
+    // The treasury must still cover every token owed, unsold ones included
+    presale_config.require_treasury_covers(ctx.accounts.treasury.amount)?;
     
     // Burn unsold tokens
     let bump = presale_config.bump;
@@ -60,7 +67,7 @@ pub fn handler(ctx: Context<BurnUnsold>) -> Result<()> {
         PRESALE_CONFIG_SEED,
         &[bump],
     ];
-    let signer = &[&seeds[..]];
+    let signer = &[seeds];
     
     let cpi_accounts = Burn {
         mint: ctx.accounts.token_mint.to_account_info(),
@@ -69,9 +76,20 @@ pub fn handler(ctx: Context<BurnUnsold>) -> Result<()> {
     };
     let cpi_program = ctx.accounts.token_program.to_account_info();
     let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-    anchor_spl::token::burn(cpi_ctx, unsold_tokens)?;
+    anchor_spl::token_interface::burn(cpi_ctx, unsold_tokens)?;
+    
+    // Burned tokens leave the sale supply, so a second burn finds none
+    let presale_config = &mut ctx.accounts.presale_config;
+    presale_config.total_tokens_for_sale = presale_config
+        .total_tokens_for_sale
+        .checked_sub(unsold_tokens)
+        .ok_or(PresaleError::MathOverflow)?;
     
     msg!("Burned {} unsold tokens", unsold_tokens);
+    emit!(BurnEvent {
+        authority: ctx.accounts.authority.key(),
+        amount: unsold_tokens,
+    });
     
     Ok(())
 }
@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, Transfer};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
 use crate::state::*;
+use crate::token_fees;
 use crate::constants::*;
 use crate::error::PresaleError;
+use crate::events::{ClaimEvent, ClaimSource};
 
 #[derive(Accounts)]
 pub struct ClaimVested<'info> {
@@ -10,6 +12,7 @@ pub struct ClaimVested<'info> {
     pub buyer: Signer<'info>,
     
     #[account(
+        mut,
         seeds = [PRESALE_CONFIG_SEED],
         bump = presale_config.bump
     )]
@@ -22,8 +25,11 @@ pub struct ClaimVested<'info> {
     )]
     pub user_purchase: Account<'info, UserPurchase>,
     
-    /// CHECK: Validated in handler
-    pub token_mint: UncheckedAccount<'info>,
+    #[account(
+        address = presale_config.token_mint @ PresaleError::InvalidTokenMint,
+        mint::token_program = token_program
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
     
     #[account(mut)]
     /// CHECK: Validated in handler
@@ -34,61 +40,167 @@ pub struct ClaimVested<'info> {
     /// CHECK: Validated in handler
     pub treasury: UncheckedAccount<'info>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVestingGrant<'info> {
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [
+            VESTING_GRANT_SEED,
+            beneficiary.key().as_ref(),
+            vesting_grant.grant_id.to_le_bytes().as_ref()
+        ],
+        bump = vesting_grant.bump
+    )]
+    pub vesting_grant: Account<'info, VestingGrant>,
+    
+    #[account(
+        address = presale_config.token_mint @ PresaleError::InvalidTokenMint,
+        mint::token_program = token_program
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    /// CHECK: Validated in handler
+    pub beneficiary_token_account: UncheckedAccount<'info>,
+    
+    /// CHECK: Treasury account that holds the tokens
+    #[account(mut)]
+    /// CHECK: Validated in handler
+    pub treasury: UncheckedAccount<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 pub fn handler(ctx: Context<ClaimVested>) -> Result<()> {
     let user_purchase = &mut ctx.accounts.user_purchase;
     let clock = Clock::get()?;
     
+    let claimable_amount = user_purchase.get_claimable_amount(clock.unix_timestamp);
+    require!(claimable_amount > 0, PresaleError::NoTokensToClaim);
+    
+    // Mark vesting entries as claimed
+    mark_claimed(&mut user_purchase.vesting_schedule, clock.unix_timestamp);
+    
+    transfer_vested(
+        &ctx.accounts.buyer,
+        &mut ctx.accounts.presale_config,
+        &ctx.accounts.token_mint,
+        &ctx.accounts.buyer_token_account,
+        &ctx.accounts.treasury,
+        &ctx.accounts.token_program,
+        claimable_amount,
+        ClaimSource::Vested,
+    )
+}
+
+pub fn handler_grant(ctx: Context<ClaimVestingGrant>) -> Result<()> {
+    let vesting_grant = &mut ctx.accounts.vesting_grant;
+    let clock = Clock::get()?;
+    
+    let claimable_amount = claimable_amount(&vesting_grant.schedule, clock.unix_timestamp);
+    require!(claimable_amount > 0, PresaleError::NoTokensToClaim);
+    
+    mark_claimed(&mut vesting_grant.schedule, clock.unix_timestamp);
+    
+    transfer_vested(
+        &ctx.accounts.beneficiary,
+        &mut ctx.accounts.presale_config,
+        &ctx.accounts.token_mint,
+        &ctx.accounts.beneficiary_token_account,
+        &ctx.accounts.treasury,
+        &ctx.accounts.token_program,
+        claimable_amount,
+        ClaimSource::VestingGrant,
+    )
+}
+
+/// Validates the claimant's token account and the treasury, then pays
+/// `claimable_amount` out of the treasury.
+#[allow(clippy::too_many_arguments)]
+fn transfer_vested<'info>(
+    claimant: &Signer<'info>,
+    presale_config: &mut Account<'info, PresaleConfig>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    claimant_token_account: &UncheckedAccount<'info>,
+    treasury: &UncheckedAccount<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    claimable_amount: u64,
+    source: ClaimSource,
+) -> Result<()> {
     // Validate unchecked accounts
-    let buyer_token_account_data = anchor_spl::token::TokenAccount::try_deserialize(&mut &ctx.accounts.buyer_token_account.data.borrow()[..])?;
+    let claimant_token_account_data = TokenAccount::try_deserialize(&mut &claimant_token_account.data.borrow()[..])?;
     require!(
-        buyer_token_account_data.owner == ctx.accounts.buyer.key(),
+        claimant_token_account_data.owner == claimant.key(),
         PresaleError::Unauthorized
     );
     require!(
-        buyer_token_account_data.mint == ctx.accounts.token_mint.key(),
+        claimant_token_account_data.mint == token_mint.key(),
         PresaleError::InvalidTokenMint
     );
     
     require!(
-        ctx.accounts.treasury.key() == ctx.accounts.presale_config.treasury,
+        treasury.key() == presale_config.treasury,
         PresaleError::InvalidTreasury
     );
     
-    let claimable_amount = user_purchase.get_claimable_amount(clock.unix_timestamp);
-    require!(claimable_amount > 0, PresaleError::NoTokensToClaim);
-    
-    // Mark vesting entries as claimed
-    for entry in &mut user_purchase.vesting_schedule {
-        if !entry.claimed 
-            && clock.unix_timestamp >= entry.release_time 
-            && entry.amount > 0 
-        {
-            entry.claimed = true;
-        }
-    }
-    
-    // Transfer tokens from treasury to buyer
-    let bump = ctx.accounts.presale_config.bump;
+    // Transfer tokens from treasury to claimant
+    let bump = presale_config.bump;
     let seeds: &[&[u8]] = &[
         PRESALE_CONFIG_SEED,
         &[bump],
     ];
-    let signer = &[&seeds[..]];
+    let signer = &[seeds];
+    
+    let claimant_token_account = claimant_token_account.to_account_info();
+    let balance_before = token_fees::token_balance(&claimant_token_account)?;
     
-    let cpi_accounts = Transfer {
-        from: ctx.accounts.treasury.to_account_info(),
-        to: ctx.accounts.buyer_token_account.to_account_info(),
-        authority: ctx.accounts.presale_config.to_account_info(),
+    let cpi_accounts = TransferChecked {
+        from: treasury.to_account_info(),
+        mint: token_mint.to_account_info(),
+        to: claimant_token_account.clone(),
+        authority: presale_config.to_account_info(),
     };
-    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_program = token_program.to_account_info();
     let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-    anchor_spl::token::transfer(cpi_ctx, claimable_amount)?;
+    anchor_spl::token_interface::transfer_checked(
+        cpi_ctx,
+        claimable_amount,
+        token_mint.decimals,
+    )?;
+    
+    presale_config.stats.record_claim(claimable_amount)?;
     
-    msg!("Claimed {} vested tokens", claimable_amount);
+    // Token-2022 transfer fees are withheld from what the claimant receives
+    let received_amount = token_fees::token_balance(&claimant_token_account)?
+        .checked_sub(balance_before)
+        .ok_or(PresaleError::MathOverflow)?;
+    
+    msg!(
+        "Claimed {} vested tokens ({} received after transfer fees)",
+        claimable_amount,
+        received_amount
+    );
+    emit!(ClaimEvent {
+        wallet: claimant.key(),
+        source,
+        amount: claimable_amount,
+        received_amount,
+        sol_paid: 0,
+        usdc_paid: 0,
+    });
     
     Ok(())
 }
-
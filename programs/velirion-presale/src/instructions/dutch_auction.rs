@@ -0,0 +1,395 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer as SystemTransfer};
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::events::{ClaimEvent, ClaimSource, PurchaseEvent, SaleRound};
+use crate::payouts;
+use crate::pricing::token_unit;
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CreateDutchAuction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = DutchAuction::SIZE,
+        seeds = [DUTCH_AUCTION_SEED, round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub dutch_auction: Account<'info, DutchAuction>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitDutchAuction<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [DUTCH_AUCTION_SEED, dutch_auction.round_id.to_le_bytes().as_ref()],
+        bump = dutch_auction.bump
+    )]
+    pub dutch_auction: Account<'info, DutchAuction>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = AuctionBid::SIZE,
+        seeds = [AUCTION_BID_SEED, dutch_auction.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub auction_bid: Account<'info, AuctionBid>,
+    
+    /// Purchases outside the auction count toward the wallet limit
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserPurchase::SIZE,
+        seeds = [USER_PURCHASE_SEED, buyer.key().as_ref()],
+        bump
+    )]
+    pub user_purchase: Account<'info, UserPurchase>,
+    
+    /// CHECK: SOL vault for receiving SOL payments
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleDutchAuction<'info> {
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [DUTCH_AUCTION_SEED, dutch_auction.round_id.to_le_bytes().as_ref()],
+        bump = dutch_auction.bump
+    )]
+    pub dutch_auction: Account<'info, DutchAuction>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDutchAuction<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        seeds = [DUTCH_AUCTION_SEED, dutch_auction.round_id.to_le_bytes().as_ref()],
+        bump = dutch_auction.bump
+    )]
+    pub dutch_auction: Account<'info, DutchAuction>,
+    
+    #[account(
+        mut,
+        seeds = [AUCTION_BID_SEED, dutch_auction.key().as_ref(), buyer.key().as_ref()],
+        bump = auction_bid.bump
+    )]
+    pub auction_bid: Account<'info, AuctionBid>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserPurchase::SIZE,
+        seeds = [USER_PURCHASE_SEED, buyer.key().as_ref()],
+        bump
+    )]
+    pub user_purchase: Account<'info, UserPurchase>,
+    
+    /// CHECK: SOL vault that pays out refunds
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<CreateDutchAuction>,
+    round_id: u64,
+    ceiling_price: u64,
+    floor_price: u64,
+    price_denominator: u64,
+    start_time: i64,
+    end_time: i64,
+    price_step: i64,
+    tokens_allocated: u64,
+    uniform_clearing: bool,
+) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    
+    require!(
+        floor_price > 0 && floor_price <= ceiling_price,
+        PresaleError::InvalidPhaseConfig
+    );
+    require!(
+        start_time < end_time && price_step > 0,
+        PresaleError::InvalidPhaseConfig
+    );
+    // Reserved until settlement so the fixed-price phases cannot sell it
+    presale_config.reserve_round_tokens(tokens_allocated)?;
+    
+    let dutch_auction = &mut ctx.accounts.dutch_auction;
+    dutch_auction.round_id = round_id;
+    dutch_auction.ceiling_price = ceiling_price;
+    dutch_auction.floor_price = floor_price;
+    // Prices without an explicit denominator are quoted per whole token
+    dutch_auction.price_denominator = if price_denominator == 0 {
+        token_unit(presale_config.token_decimals)?
+    } else {
+        price_denominator
+    };
+    dutch_auction.start_time = start_time;
+    dutch_auction.end_time = end_time;
+    dutch_auction.price_step = price_step;
+    dutch_auction.tokens_allocated = tokens_allocated;
+    dutch_auction.tokens_sold = 0;
+    dutch_auction.uniform_clearing = uniform_clearing;
+    dutch_auction.last_price = 0;
+    dutch_auction.clearing_price = 0;
+    dutch_auction.is_settled = false;
+    dutch_auction.bump = ctx.bumps.dutch_auction;
+    
+    msg!(
+        "Dutch auction {} created: {} tokens from {} down to {}",
+        round_id,
+        tokens_allocated,
+        ceiling_price,
+        floor_price
+    );
+    Ok(())
+}
+
+pub fn handler_commit(
+    ctx: Context<CommitDutchAuction>,
+    token_amount: u64,
+    max_price: Option<u64>,
+) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let dutch_auction = &mut ctx.accounts.dutch_auction;
+    let auction_bid = &mut ctx.accounts.auction_bid;
+    let user_purchase = &mut ctx.accounts.user_purchase;
+    let clock = Clock::get()?;
+    
+    require!(!presale_config.is_paused, PresaleError::PresalePaused);
+    require!(
+        dutch_auction.is_active(clock.unix_timestamp),
+        PresaleError::AuctionNotActive
+    );
+    
+    let available = dutch_auction.tokens_allocated - dutch_auction.tokens_sold;
+    require!(
+        token_amount <= available,
+        PresaleError::InsufficientTokensInPhase
+    );
+    require!(
+        token_amount <= presale_config.max_purchase_per_transaction,
+        PresaleError::ExceedsMaxPerTransaction
+    );
+    let bid_total = auction_bid
+        .token_amount
+        .checked_add(token_amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    let wallet_total = user_purchase
+        .total_purchased
+        .checked_add(bid_total)
+        .ok_or(PresaleError::MathOverflow)?;
+    require!(
+        wallet_total <= presale_config.max_purchase_per_wallet,
+        PresaleError::ExceedsMaxPerWallet
+    );
+    
+    let price = dutch_auction.current_price(clock.unix_timestamp)?;
+    if let Some(max_price) = max_price {
+        require!(price <= max_price, PresaleError::SlippageExceeded);
+    }
+    let payment_amount = dutch_auction.payment_for(price, token_amount)?;
+    require!(payment_amount > 0, PresaleError::InsufficientPayment);
+    
+    let cpi_accounts = SystemTransfer {
+        from: ctx.accounts.buyer.to_account_info(),
+        to: ctx.accounts.sol_vault.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.system_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    transfer(cpi_ctx, payment_amount)?;
+    
+    if auction_bid.bidder == Pubkey::default() {
+        auction_bid.bidder = ctx.accounts.buyer.key();
+        auction_bid.bump = ctx.bumps.auction_bid;
+    }
+    if user_purchase.wallet == Pubkey::default() {
+        user_purchase.wallet = ctx.accounts.buyer.key();
+        user_purchase.bump = ctx.bumps.user_purchase;
+    }
+    auction_bid.token_amount = bid_total;
+    auction_bid.payment_amount = auction_bid
+        .payment_amount
+        .checked_add(payment_amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    
+    dutch_auction.tokens_sold += token_amount;
+    dutch_auction.last_price = price;
+    presale_config.release_round_tokens(token_amount, token_amount)?;
+    
+    msg!(
+        "Auction bid: {} tokens at {} for {} lamports",
+        token_amount,
+        price,
+        payment_amount
+    );
+    emit!(PurchaseEvent {
+        buyer: ctx.accounts.buyer.key(),
+        round: SaleRound::DutchAuction,
+        phase: 0,
+        phases_filled: 0,
+        token_amount,
+        payment_amount,
+        payment_type: PaymentType::Sol,
+        payment_mint: anchor_spl::token::spl_token::native_mint::ID,
+        price,
+        discount_bps: 0,
+        bonus_tokens: 0,
+        referrer: None,
+    });
+    Ok(())
+}
+
+pub fn handler_settle(ctx: Context<SettleDutchAuction>) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let dutch_auction = &mut ctx.accounts.dutch_auction;
+    let clock = Clock::get()?;
+    
+    require!(!dutch_auction.is_settled, PresaleError::AuctionNotActive);
+    require!(
+        dutch_auction.has_ended(clock.unix_timestamp),
+        PresaleError::AuctionStillRunning
+    );
+    
+    // The price only falls, so the latest bid paid the lowest price
+    dutch_auction.clearing_price = if dutch_auction.tokens_sold > 0 {
+        dutch_auction.last_price
+    } else {
+        dutch_auction.floor_price
+    };
+    dutch_auction.is_settled = true;
+    
+    // Whatever the auction did not sell goes back to the unsold supply
+    presale_config.release_round_tokens(
+        dutch_auction.tokens_allocated - dutch_auction.tokens_sold,
+        0,
+    )?;
+    
+    msg!("Dutch auction settled at {}", dutch_auction.clearing_price);
+    Ok(())
+}
+
+pub fn handler_claim(ctx: Context<ClaimDutchAuction>) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let dutch_auction = &ctx.accounts.dutch_auction;
+    let auction_bid = &mut ctx.accounts.auction_bid;
+    let user_purchase = &mut ctx.accounts.user_purchase;
+    
+    require!(dutch_auction.is_settled, PresaleError::AuctionNotSettled);
+    require!(!auction_bid.claimed, PresaleError::AlreadyClaimed);
+    
+    // With uniform clearing every bid pays the clearing price
+    let cost = if dutch_auction.uniform_clearing {
+        dutch_auction
+            .payment_for(dutch_auction.clearing_price, auction_bid.token_amount)?
+            .min(auction_bid.payment_amount)
+    } else {
+        auction_bid.payment_amount
+    };
+    let refund_amount = auction_bid.payment_amount - cost;
+    
+    presale_config.stats.record_buyer(user_purchase.total_purchased, auction_bid.token_amount)?;
+    presale_config.stats.record_raised(PaymentType::Sol, cost)?;
+    presale_config.stats.record_refund(PaymentType::Sol, refund_amount)?;
+    
+    if user_purchase.wallet == Pubkey::default() {
+        user_purchase.wallet = ctx.accounts.buyer.key();
+        user_purchase.bump = ctx.bumps.user_purchase;
+    }
+    user_purchase.total_purchased = user_purchase
+        .total_purchased
+        .checked_add(auction_bid.token_amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    user_purchase.total_spent_sol = user_purchase
+        .total_spent_sol
+        .checked_add(cost)
+        .ok_or(PresaleError::MathOverflow)?;
+    user_purchase.add_vesting(
+        auction_bid.token_amount,
+        presale_config.launch_timestamp,
+        presale_config.vesting_launch_percentage,
+        presale_config.vesting_monthly_percentage,
+    )?;
+    auction_bid.claimed = true;
+    
+    if refund_amount > 0 {
+        payouts::pay_from_sol_vault(
+            &ctx.accounts.sol_vault.to_account_info(),
+            &ctx.accounts.buyer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.bumps.sol_vault,
+            refund_amount,
+        )?;
+    }
+    
+    msg!(
+        "Auction claim: {} tokens vested, {} lamports refunded",
+        auction_bid.token_amount,
+        refund_amount
+    );
+    emit!(ClaimEvent {
+        wallet: ctx.accounts.buyer.key(),
+        source: ClaimSource::DutchAuction,
+        amount: auction_bid.token_amount,
+        received_amount: 0,
+        sol_paid: refund_amount,
+        usdc_paid: 0,
+    });
+    Ok(())
+}
@@ -0,0 +1,220 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::events::{ConfigSection, ConfigUpdatedEvent};
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+#[derive(Accounts)]
+pub struct InitializeExchangeRate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = ExchangeRate::SIZE,
+        seeds = [EXCHANGE_RATE_SEED],
+        bump
+    )]
+    pub exchange_rate: Account<'info, ExchangeRate>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateExchangeRate<'info> {
+    pub signer: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [EXCHANGE_RATE_SEED],
+        bump = exchange_rate.bump,
+        constraint = signer.key() == presale_config.authority
+            || signer.key() == exchange_rate.rate_updater @ PresaleError::Unauthorized
+    )]
+    pub exchange_rate: Account<'info, ExchangeRate>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureExchangeRate<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [EXCHANGE_RATE_SEED],
+        bump = exchange_rate.bump
+    )]
+    pub exchange_rate: Account<'info, ExchangeRate>,
+}
+
+pub fn handler(
+    ctx: Context<InitializeExchangeRate>,
+    rate_updater: Pubkey,
+    rate: u64,
+    max_deviation_bps: u16,
+    max_age: i64,
+    min_update_interval: i64,
+) -> Result<()> {
+    validate_settings(max_deviation_bps, max_age, min_update_interval)?;
+    validate_rate(rate)?;
+    
+    let exchange_rate = &mut ctx.accounts.exchange_rate;
+    exchange_rate.rate_updater = rate_updater;
+    exchange_rate.rate = rate;
+    exchange_rate.updated_at = Clock::get()?.unix_timestamp;
+    exchange_rate.max_deviation_bps = max_deviation_bps;
+    exchange_rate.max_age = max_age;
+    exchange_rate.min_update_interval = min_update_interval;
+    exchange_rate.bump = ctx.bumps.exchange_rate;
+    
+    ctx.accounts.presale_config.use_exchange_rate = true;
+    
+    msg!("Exchange rate initialized at {}", rate);
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: exchange_rate_section(
+            &ctx.accounts.exchange_rate,
+            ctx.accounts.presale_config.use_exchange_rate,
+        ),
+    });
+    Ok(())
+}
+
+pub fn handler_update(ctx: Context<UpdateExchangeRate>, rate: u64) -> Result<()> {
+    validate_rate(rate)?;
+    
+    let is_authority = ctx.accounts.signer.key() == ctx.accounts.presale_config.authority;
+    let exchange_rate = &mut ctx.accounts.exchange_rate;
+    let clock = Clock::get()?;
+    
+    // The authority may reset the rate freely; the updater is bounded per
+    // update, and updates are spaced out so the bound cannot be stacked
+    if !is_authority {
+        let elapsed = clock
+            .unix_timestamp
+            .checked_sub(exchange_rate.updated_at)
+            .ok_or(PresaleError::MathOverflow)?;
+        require!(
+            elapsed >= exchange_rate.min_update_interval,
+            PresaleError::RateUpdateTooSoon
+        );
+        let change = (rate as i128 - exchange_rate.rate as i128).unsigned_abs();
+        require!(
+            change * BPS_DENOMINATOR
+                <= exchange_rate.rate as u128 * exchange_rate.max_deviation_bps as u128,
+            PresaleError::RateDeviationTooLarge
+        );
+    }
+    
+    exchange_rate.rate = rate;
+    exchange_rate.updated_at = clock.unix_timestamp;
+    
+    msg!("Exchange rate updated to {}", rate);
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.signer.key(),
+        section: exchange_rate_section(
+            &ctx.accounts.exchange_rate,
+            ctx.accounts.presale_config.use_exchange_rate,
+        ),
+    });
+    Ok(())
+}
+
+pub fn handler_configure(
+    ctx: Context<ConfigureExchangeRate>,
+    rate_updater: Option<Pubkey>,
+    max_deviation_bps: Option<u16>,
+    max_age: Option<i64>,
+    min_update_interval: Option<i64>,
+    use_exchange_rate: Option<bool>,
+) -> Result<()> {
+    let exchange_rate = &mut ctx.accounts.exchange_rate;
+    
+    if let Some(rate_updater) = rate_updater {
+        exchange_rate.rate_updater = rate_updater;
+    }
+    if let Some(max_deviation_bps) = max_deviation_bps {
+        exchange_rate.max_deviation_bps = max_deviation_bps;
+    }
+    if let Some(max_age) = max_age {
+        exchange_rate.max_age = max_age;
+    }
+    if let Some(min_update_interval) = min_update_interval {
+        exchange_rate.min_update_interval = min_update_interval;
+    }
+    validate_settings(
+        exchange_rate.max_deviation_bps,
+        exchange_rate.max_age,
+        exchange_rate.min_update_interval,
+    )?;
+    
+    if let Some(use_exchange_rate) = use_exchange_rate {
+        ctx.accounts.presale_config.use_exchange_rate = use_exchange_rate;
+    }
+    
+    msg!("Exchange rate settings updated");
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: exchange_rate_section(
+            &ctx.accounts.exchange_rate,
+            ctx.accounts.presale_config.use_exchange_rate,
+        ),
+    });
+    Ok(())
+}
+
+fn validate_rate(rate: u64) -> Result<()> {
+    require!(
+        rate > 0 && rate <= i64::MAX as u64,
+        PresaleError::InvalidExchangeRate
+    );
+    Ok(())
+}
+
+/// The updater must be able to refresh the rate before it goes stale.
+fn validate_settings(max_deviation_bps: u16, max_age: i64, min_update_interval: i64) -> Result<()> {
+    require!(
+        max_deviation_bps as u128 <= BPS_DENOMINATOR && max_age > 0,
+        PresaleError::InvalidExchangeRate
+    );
+    require!(
+        min_update_interval > 0 && min_update_interval <= max_age,
+        PresaleError::InvalidExchangeRate
+    );
+    Ok(())
+}
+
+fn exchange_rate_section(exchange_rate: &ExchangeRate, use_exchange_rate: bool) -> ConfigSection {
+    ConfigSection::ExchangeRate {
+        rate_updater: exchange_rate.rate_updater,
+        rate: exchange_rate.rate,
+        max_deviation_bps: exchange_rate.max_deviation_bps,
+        max_age: exchange_rate.max_age,
+        min_update_interval: exchange_rate.min_update_interval,
+        use_exchange_rate,
+    }
+}
@@ -0,0 +1,436 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer as SystemTransfer};
+use anchor_spl::token_interface::{Mint, TokenInterface, TransferChecked};
+use crate::state::*;
+use crate::token_fees;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::events::{ClaimEvent, ClaimSource, DepositEvent, SaleRound};
+use crate::payouts;
+use crate::pricing::token_unit;
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CreateFairLaunch<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = FairLaunch::SIZE,
+        seeds = [FAIR_LAUNCH_SEED, round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub fair_launch: Account<'info, FairLaunch>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositFairLaunch<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [FAIR_LAUNCH_SEED, fair_launch.round_id.to_le_bytes().as_ref()],
+        bump = fair_launch.bump
+    )]
+    pub fair_launch: Account<'info, FairLaunch>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = FairLaunchDeposit::SIZE,
+        seeds = [FAIR_LAUNCH_DEPOSIT_SEED, fair_launch.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub fair_launch_deposit: Account<'info, FairLaunchDeposit>,
+    
+    /// CHECK: SOL vault for receiving SOL payments
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    
+    /// USDC mint, required for USDC deposits
+    #[account(address = presale_config.usdc_mint @ PresaleError::InvalidTokenMint)]
+    pub usdc_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// CHECK: Must be the presale's USDC vault
+    #[account(mut, address = presale_config.usdc_vault @ PresaleError::InvalidPaymentVault)]
+    pub usdc_vault: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Buyer's USDC account; the token program checks mint and authority
+    #[account(mut)]
+    pub buyer_usdc_account: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SettleFairLaunch<'info> {
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [FAIR_LAUNCH_SEED, fair_launch.round_id.to_le_bytes().as_ref()],
+        bump = fair_launch.bump
+    )]
+    pub fair_launch: Account<'info, FairLaunch>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFairLaunch<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        seeds = [FAIR_LAUNCH_SEED, fair_launch.round_id.to_le_bytes().as_ref()],
+        bump = fair_launch.bump
+    )]
+    pub fair_launch: Account<'info, FairLaunch>,
+    
+    #[account(
+        mut,
+        seeds = [FAIR_LAUNCH_DEPOSIT_SEED, fair_launch.key().as_ref(), buyer.key().as_ref()],
+        bump = fair_launch_deposit.bump
+    )]
+    pub fair_launch_deposit: Account<'info, FairLaunchDeposit>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserPurchase::SIZE,
+        seeds = [USER_PURCHASE_SEED, buyer.key().as_ref()],
+        bump
+    )]
+    pub user_purchase: Account<'info, UserPurchase>,
+    
+    /// CHECK: SOL vault that pays out refunds
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    
+    /// USDC mint, required to refund USDC
+    #[account(address = presale_config.usdc_mint @ PresaleError::InvalidTokenMint)]
+    pub usdc_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// CHECK: Must be the presale's USDC vault
+    #[account(mut, address = presale_config.usdc_vault @ PresaleError::InvalidPaymentVault)]
+    pub usdc_vault: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Buyer's USDC account; the token program checks the mint
+    #[account(mut)]
+    pub buyer_usdc_account: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<CreateFairLaunch>,
+    round_id: u64,
+    price_sol: u64,
+    price_usdc: u64,
+    price_denominator: u64,
+    start_time: i64,
+    end_time: i64,
+    tokens_allocated: u64,
+) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    
+    require!(
+        price_sol > 0 && price_usdc > 0 && start_time < end_time,
+        PresaleError::InvalidPhaseConfig
+    );
+    // Reserved until settlement so the fixed-price phases cannot sell it
+    presale_config.reserve_round_tokens(tokens_allocated)?;
+    
+    let fair_launch = &mut ctx.accounts.fair_launch;
+    fair_launch.round_id = round_id;
+    fair_launch.price_sol = price_sol;
+    fair_launch.price_usdc = price_usdc;
+    // Prices without an explicit denominator are quoted per whole token
+    fair_launch.price_denominator = if price_denominator == 0 {
+        token_unit(presale_config.token_decimals)?
+    } else {
+        price_denominator
+    };
+    fair_launch.start_time = start_time;
+    fair_launch.end_time = end_time;
+    fair_launch.tokens_allocated = tokens_allocated;
+    fair_launch.total_demand = 0;
+    fair_launch.total_sol_deposited = 0;
+    fair_launch.total_usdc_deposited = 0;
+    fair_launch.tokens_filled = 0;
+    fair_launch.is_settled = false;
+    fair_launch.bump = ctx.bumps.fair_launch;
+    
+    msg!("Fair launch {} created for {} tokens", round_id, tokens_allocated);
+    Ok(())
+}
+
+pub fn handler_deposit(
+    ctx: Context<DepositFairLaunch>,
+    amount: u64,
+    payment_mint: Pubkey,
+) -> Result<()> {
+    let presale_config = &ctx.accounts.presale_config;
+    let clock = Clock::get()?;
+    
+    require!(!presale_config.is_paused, PresaleError::PresalePaused);
+    require!(
+        ctx.accounts.fair_launch.is_open(clock.unix_timestamp),
+        PresaleError::DepositWindowClosed
+    );
+    
+    let payment_type = presale_config.payment_type_for(&payment_mint);
+    let received_amount = match payment_type {
+        PaymentType::Sol => {
+            let cpi_accounts = SystemTransfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.sol_vault.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.system_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            transfer(cpi_ctx, amount)?;
+            amount
+        }
+        PaymentType::Usdc => {
+            let usdc_mint = ctx.accounts.usdc_mint.as_ref().ok_or(PresaleError::InvalidTokenMint)?;
+            let usdc_vault = ctx
+                .accounts
+                .usdc_vault
+                .as_ref()
+                .ok_or(PresaleError::InvalidPaymentVault)?
+                .to_account_info();
+            let buyer_usdc_account = ctx
+                .accounts
+                .buyer_usdc_account
+                .as_ref()
+                .ok_or(PresaleError::InvalidTokenMint)?;
+            let vault_balance_before = token_fees::token_balance(&usdc_vault)?;
+    
+            let cpi_accounts = TransferChecked {
+                from: buyer_usdc_account.to_account_info(),
+                mint: usdc_mint.to_account_info(),
+                to: usdc_vault.clone(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            anchor_spl::token_interface::transfer_checked(cpi_ctx, amount, usdc_mint.decimals)?;
+    
+            token_fees::token_balance(&usdc_vault)?
+                .checked_sub(vault_balance_before)
+                .ok_or(PresaleError::MathOverflow)?
+        }
+        PaymentType::Spl => return err!(PresaleError::InvalidPaymentType),
+    };
+    
+    let fair_launch = &mut ctx.accounts.fair_launch;
+    let demand = fair_launch.demand_for(payment_type, received_amount)?;
+    require!(demand > 0, PresaleError::InsufficientPayment);
+    
+    let deposit = &mut ctx.accounts.fair_launch_deposit;
+    if deposit.depositor == Pubkey::default() {
+        deposit.depositor = ctx.accounts.buyer.key();
+        deposit.bump = ctx.bumps.fair_launch_deposit;
+    }
+    if payment_type == PaymentType::Sol {
+        deposit.sol_amount = deposit
+            .sol_amount
+            .checked_add(received_amount)
+            .ok_or(PresaleError::MathOverflow)?;
+        fair_launch.total_sol_deposited = fair_launch
+            .total_sol_deposited
+            .checked_add(received_amount)
+            .ok_or(PresaleError::MathOverflow)?;
+    } else {
+        deposit.usdc_amount = deposit
+            .usdc_amount
+            .checked_add(received_amount)
+            .ok_or(PresaleError::MathOverflow)?;
+        fair_launch.total_usdc_deposited = fair_launch
+            .total_usdc_deposited
+            .checked_add(received_amount)
+            .ok_or(PresaleError::MathOverflow)?;
+    }
+    deposit.demand = deposit
+        .demand
+        .checked_add(demand)
+        .ok_or(PresaleError::MathOverflow)?;
+    fair_launch.total_demand = fair_launch
+        .total_demand
+        .checked_add(demand)
+        .ok_or(PresaleError::MathOverflow)?;
+    
+    msg!(
+        "Fair launch deposit: {} ({:?}) for {} tokens of demand",
+        received_amount,
+        payment_type,
+        demand
+    );
+    emit!(DepositEvent {
+        wallet: ctx.accounts.buyer.key(),
+        round: SaleRound::FairLaunch,
+        payment_type,
+        amount: received_amount,
+        demand,
+    });
+    Ok(())
+}
+
+pub fn handler_settle(ctx: Context<SettleFairLaunch>) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let fair_launch = &mut ctx.accounts.fair_launch;
+    let clock = Clock::get()?;
+    
+    require!(!fair_launch.is_settled, PresaleError::RoundAlreadySettled);
+    require!(
+        clock.unix_timestamp > fair_launch.end_time,
+        PresaleError::DepositWindowOpen
+    );
+    
+    // Every depositor is filled at the same ratio: tokens_filled / total_demand
+    fair_launch.tokens_filled = fair_launch.total_demand.min(fair_launch.tokens_allocated);
+    fair_launch.is_settled = true;
+    // The unfilled part of the allocation returns to the unsold supply
+    presale_config.release_round_tokens(fair_launch.tokens_allocated, fair_launch.tokens_filled)?;
+    
+    msg!(
+        "Fair launch settled: {} of {} tokens demanded are filled",
+        fair_launch.tokens_filled,
+        fair_launch.total_demand
+    );
+    Ok(())
+}
+
+pub fn handler_claim(ctx: Context<ClaimFairLaunch>) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let fair_launch = &ctx.accounts.fair_launch;
+    let deposit = &mut ctx.accounts.fair_launch_deposit;
+    let user_purchase = &mut ctx.accounts.user_purchase;
+    
+    require!(fair_launch.is_settled, PresaleError::RoundNotSettled);
+    require!(!deposit.claimed, PresaleError::AlreadyClaimed);
+    
+    let token_amount = fair_launch.filled_tokens(deposit.demand)?;
+    let sol_used = fair_launch.used_deposit(deposit.sol_amount)?.min(deposit.sol_amount);
+    let usdc_used = fair_launch.used_deposit(deposit.usdc_amount)?.min(deposit.usdc_amount);
+    let sol_refund = deposit.sol_amount - sol_used;
+    let usdc_refund = deposit.usdc_amount - usdc_used;
+    
+    presale_config.stats.record_buyer(user_purchase.total_purchased, token_amount)?;
+    presale_config.stats.record_raised(PaymentType::Sol, sol_used)?;
+    presale_config.stats.record_raised(PaymentType::Usdc, usdc_used)?;
+    presale_config.stats.record_refund(PaymentType::Sol, sol_refund)?;
+    presale_config.stats.record_refund(PaymentType::Usdc, usdc_refund)?;
+    
+    if user_purchase.wallet == Pubkey::default() {
+        user_purchase.wallet = ctx.accounts.buyer.key();
+        user_purchase.bump = ctx.bumps.user_purchase;
+    }
+    user_purchase.total_purchased = user_purchase
+        .total_purchased
+        .checked_add(token_amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    user_purchase.total_spent_sol = user_purchase
+        .total_spent_sol
+        .checked_add(sol_used)
+        .ok_or(PresaleError::MathOverflow)?;
+    user_purchase.total_spent_usdc = user_purchase
+        .total_spent_usdc
+        .checked_add(usdc_used)
+        .ok_or(PresaleError::MathOverflow)?;
+    user_purchase.add_vesting(
+        token_amount,
+        presale_config.launch_timestamp,
+        presale_config.vesting_launch_percentage,
+        presale_config.vesting_monthly_percentage,
+    )?;
+    deposit.claimed = true;
+    
+    if sol_refund > 0 {
+        payouts::pay_from_sol_vault(
+            &ctx.accounts.sol_vault.to_account_info(),
+            &ctx.accounts.buyer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.bumps.sol_vault,
+            sol_refund,
+        )?;
+    }
+    if usdc_refund > 0 {
+        let usdc_mint = ctx.accounts.usdc_mint.as_ref().ok_or(PresaleError::InvalidTokenMint)?;
+        let usdc_vault = ctx
+            .accounts
+            .usdc_vault
+            .as_ref()
+            .ok_or(PresaleError::InvalidPaymentVault)?;
+        let buyer_usdc_account = ctx
+            .accounts
+            .buyer_usdc_account
+            .as_ref()
+            .ok_or(PresaleError::InvalidTokenMint)?;
+        payouts::pay_from_token_vault(
+            &usdc_vault.to_account_info(),
+            usdc_mint,
+            &buyer_usdc_account.to_account_info(),
+            &presale_config.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            presale_config.bump,
+            usdc_refund,
+        )?;
+    }
+    
+    msg!(
+        "Fair launch claim: {} tokens vested, refunded {} lamports and {} USDC",
+        token_amount,
+        sol_refund,
+        usdc_refund
+    );
+    emit!(ClaimEvent {
+        wallet: ctx.accounts.buyer.key(),
+        source: ClaimSource::FairLaunch,
+        amount: token_amount,
+        received_amount: 0,
+        sol_paid: sol_refund,
+        usdc_paid: usdc_refund,
+    });
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::constants::*;
+use crate::pricing::token_unit;
 
 #[derive(Accounts)]
 pub struct GetPurchaseStatus<'info> {
@@ -13,37 +14,137 @@ pub struct GetPurchaseStatus<'info> {
     /// CHECK: User wallet to check status for
     pub user_wallet: UncheckedAccount<'info>,
     
+    /// CHECK: The wallet's purchase PDA; may not exist yet
     #[account(
         seeds = [USER_PURCHASE_SEED, user_wallet.key().as_ref()],
         bump
     )]
-    pub user_purchase: Account<'info, UserPurchase>,
+    pub user_purchase: UncheckedAccount<'info>,
 }
 
-pub fn handler(ctx: Context<GetPurchaseStatus>) -> Result<()> {
+#[derive(Accounts)]
+pub struct GetSaleStats<'info> {
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+}
+
+/// Returned to the caller so clients can read the status without parsing logs.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PurchaseStatus {
+    pub has_purchased: bool, // false when the wallet has no purchase account yet
+    pub total_purchased: u64,
+    pub total_spent_sol: u64,
+    pub total_spent_usdc: u64,
+    pub claimable: u64,
+    pub remaining_allocation: u64,
+    pub next_unlock_time: Option<i64>, // next unclaimed entry still locked
+    pub last_purchase_time: i64,
+    pub current_phase: Option<u8>,
+    pub price_sol: Option<u64>, // current price per whole sale token; None when SOL is priced from a feed or exchange rate
+    pub price_usdc: u64,
+    pub is_paused: bool,
+}
+
+pub fn handler(ctx: Context<GetPurchaseStatus>) -> Result<PurchaseStatus> {
     let presale_config = &ctx.accounts.presale_config;
-    let user_purchase = &ctx.accounts.user_purchase;
     let clock = Clock::get()?;
     
-    // Get current phase
-    let current_phase = presale_config.get_current_phase(clock.unix_timestamp);
+    // A wallet that never bought reads as an empty purchase record
+    let user_purchase_info = &ctx.accounts.user_purchase;
+    let user_purchase = if user_purchase_info.data_is_empty() {
+        None
+    } else {
+        require_keys_eq!(*user_purchase_info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+        Some(UserPurchase::try_deserialize(&mut &user_purchase_info.data.borrow()[..])?)
+    };
     
-    // Calculate claimable amount
-    let claimable = user_purchase.get_claimable_amount(clock.unix_timestamp);
+    // Get current phase and its spot prices
+    let current_phase = presale_config.get_current_phase(clock.unix_timestamp);
+    let (price_sol, price_usdc) = match current_phase {
+        Some(index) => {
+            let phase = &presale_config.phases[index];
+            let unit = token_unit(presale_config.token_decimals)?;
+            let spot = |payment_type| -> Result<u64> {
+                let prices = presale_config.phase_price_range(index, payment_type)?;
+                phase.spot_price(&prices, clock.unix_timestamp)?.payment_for(unit)
+            };
+            // SOL priced from a feed or exchange rate has no fixed per-phase price
+            let price_sol = if presale_config.uses_sol_price_feed()
+                || presale_config.use_exchange_rate
+                || phase.price_sol == 0
+            {
+                None
+            } else {
+                Some(spot(PaymentType::Sol)?)
+            };
+            (price_sol, spot(PaymentType::Usdc)?)
+        }
+        None => (None, 0),
+    };
     
-    // Calculate remaining allocation
-    let remaining = user_purchase.get_remaining_allocation(presale_config.max_purchase_per_wallet);
+    let status = match user_purchase {
+        Some(user_purchase) => PurchaseStatus {
+            has_purchased: true,
+            total_purchased: user_purchase.total_purchased,
+            total_spent_sol: user_purchase.total_spent_sol,
+            total_spent_usdc: user_purchase.total_spent_usdc,
+            claimable: user_purchase.get_claimable_amount(clock.unix_timestamp),
+            remaining_allocation: user_purchase
+                .get_remaining_allocation(presale_config.max_purchase_per_wallet),
+            next_unlock_time: user_purchase
+                .vesting_schedule
+                .iter()
+                .filter(|entry| {
+                    !entry.claimed && entry.amount > 0 && entry.release_time > clock.unix_timestamp
+                })
+                .map(|entry| entry.release_time)
+                .min(),
+            last_purchase_time: user_purchase.last_purchase_time,
+            current_phase: current_phase.map(|index| index as u8),
+            price_sol,
+            price_usdc,
+            is_paused: presale_config.is_paused,
+        },
+        None => PurchaseStatus {
+            has_purchased: false,
+            total_purchased: 0,
+            total_spent_sol: 0,
+            total_spent_usdc: 0,
+            claimable: 0,
+            remaining_allocation: presale_config.max_purchase_per_wallet,
+            next_unlock_time: None,
+            last_purchase_time: 0,
+            current_phase: current_phase.map(|index| index as u8),
+            price_sol,
+            price_usdc,
+            is_paused: presale_config.is_paused,
+        },
+    };
     
     msg!("Purchase Status:");
-    msg!("  Total Purchased: {}", user_purchase.total_purchased);
-    msg!("  Total Spent SOL: {}", user_purchase.total_spent_sol);
-    msg!("  Total Spent USDC: {}", user_purchase.total_spent_usdc);
-    msg!("  Claimable Tokens: {}", claimable);
-    msg!("  Remaining Allocation: {}", remaining);
-    msg!("  Last Purchase Time: {}", user_purchase.last_purchase_time);
-    msg!("  Current Phase: {:?}", current_phase);
-    msg!("  Presale Paused: {}", presale_config.is_paused);
-    
-    Ok(())
+    msg!("  Total Purchased: {}", status.total_purchased);
+    msg!("  Total Spent SOL: {}", status.total_spent_sol);
+    msg!("  Total Spent USDC: {}", status.total_spent_usdc);
+    msg!("  Claimable Tokens: {}", status.claimable);
+    msg!("  Remaining Allocation: {}", status.remaining_allocation);
+    msg!("  Last Purchase Time: {}", status.last_purchase_time);
+    msg!("  Current Phase: {:?}", status.current_phase);
+    msg!("  Presale Paused: {}", status.is_paused);
+    
+    Ok(status)
 }
 
+pub fn handler_sale_stats(ctx: Context<GetSaleStats>) -> Result<SaleStats> {
+    let stats = ctx.accounts.presale_config.stats;
+    
+    msg!("Sale Stats:");
+    msg!("  Raised SOL: {}", stats.raised_sol);
+    msg!("  Raised USDC: {}", stats.raised_usdc);
+    msg!("  Unique Buyers: {}", stats.unique_buyers);
+    msg!("  Total Claimed: {}", stats.total_claimed);
+    
+    Ok(stats)
+}
@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use crate::state::*;
 use crate::constants::*;
 use crate::error::PresaleError;
@@ -9,11 +9,9 @@ pub struct Initialize<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
     
-    /// CHECK: Validated in handler
-    pub token_mint: UncheckedAccount<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
     
-    /// CHECK: Validated in handler
-    pub usdc_mint: UncheckedAccount<'info>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         init,
@@ -36,23 +34,25 @@ pub struct Initialize<'info> {
         payer = authority,
         token::mint = usdc_mint,
         token::authority = presale_config,
+        token::token_program = token_program,
         seeds = [USDC_VAULT_SEED],
         bump
     )]
-    pub usdc_vault: Account<'info, TokenAccount>,
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
     
     /// CHECK: Treasury token account that holds presale tokens (must be created separately and funded)
     /// CHECK: Validated in handler
     pub treasury: UncheckedAccount<'info>,
     
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<Initialize>,
-    phases: [PresalePhase; 10],
+    mut phases: [PresalePhase; 10],
     total_tokens_for_sale: u64,
     max_purchase_per_transaction: u64,
     max_purchase_per_wallet: u64,
@@ -60,6 +60,8 @@ pub fn handler(
     launch_timestamp: i64,
     vesting_launch_percentage: u8,
     vesting_monthly_percentage: u8,
+    allow_freeze_authority: bool,
+    allow_usdc_freeze_authority: bool,
 ) -> Result<()> {
     let presale_config = &mut ctx.accounts.presale_config;
     
@@ -69,20 +71,41 @@ pub fn handler(
         PresaleError::InvalidVestingSchedule
     );
     
+    // Prices without an explicit denominator are quoted per whole token
+    let default_denominator = crate::pricing::token_unit(ctx.accounts.token_mint.decimals)?;
+    for phase in phases.iter_mut() {
+        if phase.price_denominator == 0 {
+            phase.price_denominator = default_denominator;
+        }
+    }
+    
     // Validate phases
     for (i, phase) in phases.iter().enumerate() {
         require!(
             phase.start_time < phase.end_time,
             PresaleError::InvalidPhaseConfig
         );
+        // `price_sol` may be left at zero when SOL is priced from a USD feed
         require!(
-            phase.price_sol > 0 && phase.price_usdc > 0,
+            phase.price_usdc > 0,
             PresaleError::InvalidPhaseConfig
         );
         require!(
             phase.tokens_allocated > 0,
             PresaleError::InvalidPhaseConfig
         );
+        require!(
+            phase.bonus_bps <= 10_000,
+            PresaleError::InvalidPhaseConfig
+        );
+        
+        // Curved phases need an end price for every currency they are priced in
+        if phase.curve != PriceCurve::Flat {
+            require!(
+                phase.end_price_usdc > 0 && (phase.price_sol == 0 || phase.end_price_sol > 0),
+                PresaleError::InvalidPhaseConfig
+            );
+        }
         
         // Ensure phases are sequential
         if i > 0 {
@@ -93,8 +116,20 @@ pub fn handler(
         }
     }
     
+    // A freeze authority could lock buyers' claimed tokens or the USDC vault,
+    // so each mint's has to be accepted explicitly. Stablecoins such as USDC
+    // usually carry one, hence the separate flag.
+    require!(
+        allow_freeze_authority || ctx.accounts.token_mint.freeze_authority.is_none(),
+        PresaleError::FreezeAuthorityNotAllowed
+    );
+    require!(
+        allow_usdc_freeze_authority || ctx.accounts.usdc_mint.freeze_authority.is_none(),
+        PresaleError::FreezeAuthorityNotAllowed
+    );
+    
     // Validate token mints and treasury account
-    let treasury_data = anchor_spl::token::TokenAccount::try_deserialize(&mut &ctx.accounts.treasury.data.borrow()[..])?;
+    let treasury_data = TokenAccount::try_deserialize(&mut &ctx.accounts.treasury.data.borrow()[..])?;
     require!(
         treasury_data.mint == ctx.accounts.token_mint.key(),
         PresaleError::InvalidTokenMint
@@ -106,6 +141,8 @@ pub fn handler(
     presale_config.treasury = ctx.accounts.treasury.key();
     presale_config.sol_vault = ctx.accounts.sol_vault.key();
     presale_config.usdc_vault = ctx.accounts.usdc_vault.key();
+    presale_config.token_decimals = ctx.accounts.token_mint.decimals;
+    presale_config.usdc_decimals = ctx.accounts.usdc_mint.decimals;
     presale_config.is_paused = false;
     presale_config.total_tokens_for_sale = total_tokens_for_sale;
     presale_config.tokens_sold = 0;
@@ -116,6 +153,26 @@ pub fn handler(
     presale_config.min_time_between_purchases = min_time_between_purchases;
     presale_config.vesting_launch_percentage = vesting_launch_percentage;
     presale_config.vesting_monthly_percentage = vesting_monthly_percentage;
+    presale_config.auto_rollover = false;
+    presale_config.carry_over_unsold = false;
+    presale_config.trim_to_limits = false;
+    presale_config.sol_price_feed = Pubkey::default();
+    presale_config.max_price_age = 0;
+    presale_config.max_confidence_bps = 0;
+    presale_config.use_exchange_rate = false;
+    presale_config.round_tokens_reserved = 0;
+    presale_config.discount_tiers = [DiscountTier::default(); MAX_DISCOUNT_TIERS];
+    presale_config.referral_reward = ReferralReward::None;
+    presale_config.referral_reward_bps = 0;
+    presale_config.referral_pool = 0;
+    presale_config.referral_tokens_booked = 0;
+    presale_config.bonus_pool = 0;
+    presale_config.bonus_tokens_granted = 0;
+    presale_config.bonus_vesting_launch_percentage = vesting_launch_percentage;
+    presale_config.bonus_vesting_monthly_percentage = vesting_monthly_percentage;
+    presale_config.reserved_allocation = 0;
+    presale_config.reserved_tokens_granted = 0;
+    presale_config.stats = SaleStats::default();
     presale_config.bump = ctx.bumps.presale_config;
     
     msg!("Presale initialized with {} tokens", total_tokens_for_sale);
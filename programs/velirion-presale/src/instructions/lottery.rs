@@ -0,0 +1,380 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer as SystemTransfer};
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::events::{ClaimEvent, ClaimSource, DepositEvent, SaleRound};
+use crate::lottery_draw;
+use crate::payouts;
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CreateLottery<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = Lottery::SIZE,
+        seeds = [LOTTERY_SEED, round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterLottery<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [LOTTERY_SEED, lottery.round_id.to_le_bytes().as_ref()],
+        bump = lottery.bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+    
+    #[account(
+        init,
+        payer = buyer,
+        space = LotteryTicket::SIZE,
+        seeds = [LOTTERY_TICKET_SEED, lottery.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub lottery_ticket: Account<'info, LotteryTicket>,
+    
+    /// CHECK: SOL vault for receiving SOL payments
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealLottery<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [LOTTERY_SEED, lottery.round_id.to_le_bytes().as_ref()],
+        bump = lottery.bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLottery<'info> {
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [LOTTERY_SEED, lottery.round_id.to_le_bytes().as_ref()],
+        bump = lottery.bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLottery<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        seeds = [LOTTERY_SEED, lottery.round_id.to_le_bytes().as_ref()],
+        bump = lottery.bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+    
+    #[account(
+        mut,
+        seeds = [LOTTERY_TICKET_SEED, lottery.key().as_ref(), buyer.key().as_ref()],
+        bump = lottery_ticket.bump
+    )]
+    pub lottery_ticket: Account<'info, LotteryTicket>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserPurchase::SIZE,
+        seeds = [USER_PURCHASE_SEED, buyer.key().as_ref()],
+        bump
+    )]
+    pub user_purchase: Account<'info, UserPurchase>,
+    
+    /// CHECK: SOL vault that pays out refunds
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<CreateLottery>,
+    round_id: u64,
+    seed_commitment: [u8; 32],
+    ticket_price: u64,
+    tokens_per_winner: u64,
+    winner_count: u32,
+    start_time: i64,
+    end_time: i64,
+    reveal_deadline: i64,
+) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    
+    require!(
+        ticket_price > 0 && tokens_per_winner > 0 && winner_count > 0,
+        PresaleError::InvalidPhaseConfig
+    );
+    require!(
+        start_time < end_time && end_time < reveal_deadline,
+        PresaleError::InvalidPhaseConfig
+    );
+    let tokens_allocated = tokens_per_winner
+        .checked_mul(winner_count as u64)
+        .ok_or(PresaleError::MathOverflow)?;
+    // Reserved until the draw so the fixed-price phases cannot sell it
+    presale_config.reserve_round_tokens(tokens_allocated)?;
+    
+    let lottery = &mut ctx.accounts.lottery;
+    lottery.round_id = round_id;
+    lottery.seed_commitment = seed_commitment;
+    lottery.entrants_hash = [0; 32];
+    lottery.randomness = [0; 32];
+    lottery.ticket_price = ticket_price;
+    lottery.tokens_per_winner = tokens_per_winner;
+    lottery.winner_count = winner_count;
+    lottery.ticket_count = 0;
+    lottery.start_time = start_time;
+    lottery.end_time = end_time;
+    lottery.reveal_deadline = reveal_deadline;
+    lottery.is_revealed = false;
+    lottery.is_cancelled = false;
+    lottery.bump = ctx.bumps.lottery;
+    
+    msg!(
+        "Lottery {} created: {} winners of {} tokens",
+        round_id,
+        winner_count,
+        tokens_per_winner
+    );
+    Ok(())
+}
+
+pub fn handler_register(ctx: Context<RegisterLottery>) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+    
+    require!(!ctx.accounts.presale_config.is_paused, PresaleError::PresalePaused);
+    require!(
+        lottery.is_open(clock.unix_timestamp),
+        PresaleError::DepositWindowClosed
+    );
+    
+    let cpi_accounts = SystemTransfer {
+        from: ctx.accounts.buyer.to_account_info(),
+        to: ctx.accounts.sol_vault.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.system_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    transfer(cpi_ctx, lottery.ticket_price)?;
+    
+    let lottery_ticket = &mut ctx.accounts.lottery_ticket;
+    lottery_ticket.owner = ctx.accounts.buyer.key();
+    lottery_ticket.ticket_index = lottery.ticket_count;
+    lottery_ticket.claimed = false;
+    lottery_ticket.bump = ctx.bumps.lottery_ticket;
+    
+    lottery.ticket_count = lottery
+        .ticket_count
+        .checked_add(1)
+        .ok_or(PresaleError::MathOverflow)?;
+    lottery.entrants_hash = lottery_draw::add_entrant(&lottery.entrants_hash, ctx.accounts.buyer.key().as_ref());
+    
+    msg!("Lottery ticket {} registered", lottery_ticket.ticket_index);
+    emit!(DepositEvent {
+        wallet: ctx.accounts.buyer.key(),
+        round: SaleRound::Lottery,
+        payment_type: PaymentType::Sol,
+        amount: lottery.ticket_price,
+        demand: 0,
+    });
+    Ok(())
+}
+
+pub fn handler_reveal(ctx: Context<RevealLottery>, seed: [u8; 32]) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+    
+    require!(
+        !lottery.is_revealed && !lottery.is_cancelled,
+        PresaleError::RoundAlreadySettled
+    );
+    require!(
+        clock.unix_timestamp > lottery.end_time,
+        PresaleError::DepositWindowOpen
+    );
+    require!(
+        clock.unix_timestamp <= lottery.reveal_deadline,
+        PresaleError::DeadlineExceeded
+    );
+    require!(
+        lottery_draw::seed_commitment(&seed) == lottery.seed_commitment,
+        PresaleError::InvalidSeedReveal
+    );
+    
+    lottery.randomness = lottery_draw::draw_randomness(&seed, &lottery.entrants_hash);
+    lottery.is_revealed = true;
+    
+    // Prizes nobody entered for return to the unsold supply
+    let winners = lottery.ticket_count.min(lottery.winner_count);
+    presale_config.release_round_tokens(
+        lottery.tokens_allocated(),
+        lottery.tokens_per_winner * winners as u64,
+    )?;
+    
+    msg!(
+        "Lottery revealed: {} winners from {} tickets",
+        winners,
+        lottery.ticket_count
+    );
+    Ok(())
+}
+
+pub fn handler_cancel(ctx: Context<CancelLottery>) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+    
+    require!(
+        !lottery.is_revealed && !lottery.is_cancelled,
+        PresaleError::RoundAlreadySettled
+    );
+    require!(
+        clock.unix_timestamp > lottery.reveal_deadline,
+        PresaleError::RevealWindowOpen
+    );
+    
+    // An unrevealed seed forfeits the draw: every ticket is refunded
+    lottery.is_cancelled = true;
+    presale_config.release_round_tokens(lottery.tokens_allocated(), 0)?;
+    
+    msg!("Lottery {} cancelled: {} tickets refunded", lottery.round_id, lottery.ticket_count);
+    Ok(())
+}
+
+pub fn handler_claim(ctx: Context<ClaimLottery>) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let lottery = &ctx.accounts.lottery;
+    let lottery_ticket = &mut ctx.accounts.lottery_ticket;
+    let user_purchase = &mut ctx.accounts.user_purchase;
+    
+    require!(!lottery_ticket.claimed, PresaleError::AlreadyClaimed);
+    require!(
+        lottery.is_revealed || lottery.is_cancelled,
+        PresaleError::RoundNotSettled
+    );
+    
+    // A cancelled lottery has no winners
+    let is_winner = lottery.is_revealed
+        && lottery_draw::is_winner(
+            &lottery.randomness,
+            lottery_ticket.ticket_index,
+            lottery.ticket_count,
+            lottery.winner_count,
+        );
+    lottery_ticket.claimed = true;
+    
+    if is_winner {
+        presale_config.stats.record_buyer(user_purchase.total_purchased, lottery.tokens_per_winner)?;
+        presale_config.stats.record_raised(PaymentType::Sol, lottery.ticket_price)?;
+        if user_purchase.wallet == Pubkey::default() {
+            user_purchase.wallet = ctx.accounts.buyer.key();
+            user_purchase.bump = ctx.bumps.user_purchase;
+        }
+        user_purchase.total_purchased = user_purchase
+            .total_purchased
+            .checked_add(lottery.tokens_per_winner)
+            .ok_or(PresaleError::MathOverflow)?;
+        user_purchase.total_spent_sol = user_purchase
+            .total_spent_sol
+            .checked_add(lottery.ticket_price)
+            .ok_or(PresaleError::MathOverflow)?;
+        user_purchase.add_vesting(
+            lottery.tokens_per_winner,
+            presale_config.launch_timestamp,
+            presale_config.vesting_launch_percentage,
+            presale_config.vesting_monthly_percentage,
+        )?;
+    } else {
+        presale_config.stats.record_refund(PaymentType::Sol, lottery.ticket_price)?;
+        payouts::pay_from_sol_vault(
+            &ctx.accounts.sol_vault.to_account_info(),
+            &ctx.accounts.buyer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.bumps.sol_vault,
+            lottery.ticket_price,
+        )?;
+    }
+    
+    msg!(
+        "Lottery ticket {}: {}",
+        lottery_ticket.ticket_index,
+        if is_winner { "won" } else { "refunded" }
+    );
+    emit!(ClaimEvent {
+        wallet: ctx.accounts.buyer.key(),
+        source: ClaimSource::Lottery,
+        amount: if is_winner { lottery.tokens_per_winner } else { 0 },
+        received_amount: 0,
+        sol_paid: if is_winner { 0 } else { lottery.ticket_price },
+        usdc_paid: 0,
+    });
+    Ok(())
+}
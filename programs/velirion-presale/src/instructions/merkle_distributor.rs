@@ -0,0 +1,177 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::merkle;
+use crate::events::{ClaimEvent, ClaimSource};
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CreateMerkleDistributor<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = MerkleDistributor::SIZE,
+        seeds = [MERKLE_DISTRIBUTOR_SEED, round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub merkle_distributor: Account<'info, MerkleDistributor>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct ClaimMerkleAllocation<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [MERKLE_DISTRIBUTOR_SEED, merkle_distributor.round_id.to_le_bytes().as_ref()],
+        bump = merkle_distributor.bump
+    )]
+    pub merkle_distributor: Account<'info, MerkleDistributor>,
+    
+    /// Exists once the leaf has been claimed, so a second claim fails
+    #[account(
+        init,
+        payer = wallet,
+        space = MerkleClaim::SIZE,
+        seeds = [
+            MERKLE_CLAIM_SEED,
+            merkle_distributor.key().as_ref(),
+            merkle::leaf(&wallet.key(), amount).as_ref()
+        ],
+        bump
+    )]
+    pub merkle_claim: Account<'info, MerkleClaim>,
+    
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = UserPurchase::SIZE,
+        seeds = [USER_PURCHASE_SEED, wallet.key().as_ref()],
+        bump
+    )]
+    pub user_purchase: Account<'info, UserPurchase>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<CreateMerkleDistributor>,
+    round_id: u64,
+    root: [u8; 32],
+    total_amount: u64,
+) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    
+    require!(total_amount > 0, PresaleError::InvalidPhaseConfig);
+    
+    // The whole tree is set aside from the reserved allocation up front
+    let reserved_tokens_granted = presale_config
+        .reserved_tokens_granted
+        .checked_add(total_amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    require!(
+        reserved_tokens_granted <= presale_config.reserved_allocation,
+        PresaleError::ExceedsReservedAllocation
+    );
+    presale_config.reserved_tokens_granted = reserved_tokens_granted;
+    
+    let merkle_distributor = &mut ctx.accounts.merkle_distributor;
+    merkle_distributor.round_id = round_id;
+    merkle_distributor.root = root;
+    merkle_distributor.total_amount = total_amount;
+    merkle_distributor.claimed_amount = 0;
+    merkle_distributor.claim_count = 0;
+    merkle_distributor.bump = ctx.bumps.merkle_distributor;
+    
+    msg!("Merkle distributor {} created for {} tokens", round_id, total_amount);
+    Ok(())
+}
+
+pub fn handler_claim(
+    ctx: Context<ClaimMerkleAllocation>,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let merkle_distributor = &mut ctx.accounts.merkle_distributor;
+    let user_purchase = &mut ctx.accounts.user_purchase;
+    let wallet = ctx.accounts.wallet.key();
+    let clock = Clock::get()?;
+    
+    require!(
+        merkle::verify(&proof, &merkle_distributor.root, merkle::leaf(&wallet, amount)),
+        PresaleError::InvalidMerkleProof
+    );
+    
+    let claimed_amount = merkle_distributor
+        .claimed_amount
+        .checked_add(amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    require!(
+        claimed_amount <= merkle_distributor.total_amount,
+        PresaleError::ExceedsReservedAllocation
+    );
+    merkle_distributor.claimed_amount = claimed_amount;
+    merkle_distributor.claim_count = merkle_distributor
+        .claim_count
+        .checked_add(1)
+        .ok_or(PresaleError::MathOverflow)?;
+    
+    presale_config.stats.record_buyer(user_purchase.total_purchased, amount)?;
+    
+    // Private-sale allocations follow the public vesting schedule
+    if user_purchase.wallet == Pubkey::default() {
+        user_purchase.wallet = wallet;
+        user_purchase.bump = ctx.bumps.user_purchase;
+    }
+    user_purchase.total_purchased = user_purchase
+        .total_purchased
+        .checked_add(amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    user_purchase.add_vesting(
+        amount,
+        presale_config.launch_timestamp,
+        presale_config.vesting_launch_percentage,
+        presale_config.vesting_monthly_percentage,
+    )?;
+    
+    let merkle_claim = &mut ctx.accounts.merkle_claim;
+    merkle_claim.wallet = wallet;
+    merkle_claim.amount = amount;
+    merkle_claim.claimed_at = clock.unix_timestamp;
+    merkle_claim.bump = ctx.bumps.merkle_claim;
+    
+    msg!("Merkle allocation of {} tokens claimed by {}", amount, wallet);
+    emit!(ClaimEvent {
+        wallet,
+        source: ClaimSource::MerkleAllocation,
+        amount,
+        received_amount: 0,
+        sol_paid: 0,
+        usdc_paid: 0,
+    });
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::events::{GrantEvent, GrantKind};
+
+#[derive(Accounts)]
+#[instruction(reference: [u8; 32])]
+pub struct GrantAllocation<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    /// CHECK: Any wallet can receive a grant
+    pub beneficiary: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = OtcGrant::SIZE,
+        seeds = [OTC_GRANT_SEED, beneficiary.key().as_ref(), reference.as_ref()],
+        bump
+    )]
+    pub otc_grant: Account<'info, OtcGrant>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = UserPurchase::SIZE,
+        seeds = [USER_PURCHASE_SEED, beneficiary.key().as_ref()],
+        bump
+    )]
+    pub user_purchase: Account<'info, UserPurchase>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAllocation<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [OTC_GRANT_SEED, otc_grant.beneficiary.as_ref(), otc_grant.reference.as_ref()],
+        bump = otc_grant.bump
+    )]
+    pub otc_grant: Account<'info, OtcGrant>,
+    
+    #[account(
+        mut,
+        seeds = [USER_PURCHASE_SEED, otc_grant.beneficiary.as_ref()],
+        bump = user_purchase.bump
+    )]
+    pub user_purchase: Account<'info, UserPurchase>,
+}
+
+pub fn handler(ctx: Context<GrantAllocation>, reference: [u8; 32], token_amount: u64) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let user_purchase = &mut ctx.accounts.user_purchase;
+    let clock = Clock::get()?;
+    
+    require!(token_amount > 0, PresaleError::InvalidPhaseConfig);
+    let reserved_tokens_granted = presale_config
+        .reserved_tokens_granted
+        .checked_add(token_amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    require!(
+        reserved_tokens_granted <= presale_config.reserved_allocation,
+        PresaleError::ExceedsReservedAllocation
+    );
+    
+    if user_purchase.wallet == Pubkey::default() {
+        user_purchase.wallet = ctx.accounts.beneficiary.key();
+        user_purchase.bump = ctx.bumps.user_purchase;
+    }
+    user_purchase.add_vesting(
+        token_amount,
+        presale_config.launch_timestamp,
+        presale_config.vesting_launch_percentage,
+        presale_config.vesting_monthly_percentage,
+    )?;
+    presale_config.reserved_tokens_granted = reserved_tokens_granted;
+    
+    let otc_grant = &mut ctx.accounts.otc_grant;
+    otc_grant.beneficiary = ctx.accounts.beneficiary.key();
+    otc_grant.reference = reference;
+    otc_grant.token_amount = token_amount;
+    otc_grant.granted_at = clock.unix_timestamp;
+    otc_grant.is_revoked = false;
+    otc_grant.bump = ctx.bumps.otc_grant;
+    
+    msg!(
+        "Granted {} tokens to {}",
+        token_amount,
+        otc_grant.beneficiary
+    );
+    emit!(GrantEvent {
+        authority: ctx.accounts.authority.key(),
+        beneficiary: otc_grant.beneficiary,
+        kind: GrantKind::Allocation,
+        amount: token_amount,
+        is_revoked: false,
+    });
+    Ok(())
+}
+
+pub fn handler_revoke(ctx: Context<RevokeAllocation>) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let otc_grant = &mut ctx.accounts.otc_grant;
+    let clock = Clock::get()?;
+    
+    require!(!otc_grant.is_revoked, PresaleError::GrantRevoked);
+    // Nothing can have been claimed before launch
+    require!(
+        clock.unix_timestamp < presale_config.launch_timestamp,
+        PresaleError::AlreadyLaunched
+    );
+    
+    ctx.accounts.user_purchase.remove_vesting(
+        otc_grant.token_amount,
+        presale_config.vesting_launch_percentage,
+        presale_config.vesting_monthly_percentage,
+    )?;
+    presale_config.reserved_tokens_granted = presale_config
+        .reserved_tokens_granted
+        .checked_sub(otc_grant.token_amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    otc_grant.is_revoked = true;
+    
+    msg!(
+        "Revoked grant of {} tokens from {}",
+        otc_grant.token_amount,
+        otc_grant.beneficiary
+    );
+    emit!(GrantEvent {
+        authority: ctx.accounts.authority.key(),
+        beneficiary: otc_grant.beneficiary,
+        kind: GrantKind::Allocation,
+        amount: otc_grant.token_amount,
+        is_revoked: true,
+    });
+    Ok(())
+}
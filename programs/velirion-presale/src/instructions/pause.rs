@@ -2,6 +2,7 @@ use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::constants::*;
 use crate::error::PresaleError;
+use crate::events::PauseEvent;
 
 #[derive(Accounts)]
 pub struct Pause<'info> {
@@ -21,6 +22,10 @@ pub fn handler(ctx: Context<Pause>) -> Result<()> {
     let presale_config = &mut ctx.accounts.presale_config;
     presale_config.is_paused = true;
     msg!("Presale paused");
+    emit!(PauseEvent {
+        authority: ctx.accounts.authority.key(),
+        is_paused: true,
+    });
     Ok(())
 }
 
@@ -42,6 +47,10 @@ pub fn handler_unpause(ctx: Context<Unpause>) -> Result<()> {
     let presale_config = &mut ctx.accounts.presale_config;
     presale_config.is_paused = false;
     msg!("Presale unpaused");
+    emit!(PauseEvent {
+        authority: ctx.accounts.authority.key(),
+        is_paused: false,
+    });
     Ok(())
 }
 
@@ -0,0 +1,149 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::events::{ConfigSection, ConfigUpdatedEvent};
+
+#[derive(Accounts)]
+pub struct AddPaymentMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = PaymentMintConfig::SIZE,
+        seeds = [PAYMENT_MINT_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub payment_mint_config: Account<'info, PaymentMintConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = presale_config,
+        token::token_program = token_program,
+        seeds = [PAYMENT_VAULT_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub payment_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn handler(
+    ctx: Context<AddPaymentMint>,
+    usd_pegged: bool,
+    prices: [u64; PHASE_COUNT],
+) -> Result<()> {
+    let presale_config = &ctx.accounts.presale_config;
+    let mint = &ctx.accounts.mint;
+    
+    // SOL and USDC have their own built-in payment paths
+    require!(
+        presale_config.payment_type_for(&mint.key()) == PaymentType::Spl,
+        PresaleError::InvalidTokenMint
+    );
+    
+    if !usd_pegged {
+        require!(
+            prices.iter().all(|price| *price > 0),
+            PresaleError::InvalidPhaseConfig
+        );
+    }
+    
+    let payment_mint_config = &mut ctx.accounts.payment_mint_config;
+    payment_mint_config.mint = mint.key();
+    payment_mint_config.vault = ctx.accounts.payment_vault.key();
+    payment_mint_config.decimals = mint.decimals;
+    payment_mint_config.usd_pegged = usd_pegged;
+    payment_mint_config.prices = prices;
+    payment_mint_config.is_enabled = true;
+    payment_mint_config.total_received = 0;
+    payment_mint_config.bump = ctx.bumps.payment_mint_config;
+    
+    msg!("Payment mint {} added (usd pegged: {})", mint.key(), usd_pegged);
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::PaymentMint {
+            mint: payment_mint_config.mint,
+            is_enabled: payment_mint_config.is_enabled,
+            usd_pegged: payment_mint_config.usd_pegged,
+            prices: payment_mint_config.prices,
+        },
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdatePaymentMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [PAYMENT_MINT_SEED, payment_mint_config.mint.as_ref()],
+        bump = payment_mint_config.bump
+    )]
+    pub payment_mint_config: Account<'info, PaymentMintConfig>,
+}
+
+pub fn handler_update(
+    ctx: Context<UpdatePaymentMint>,
+    is_enabled: Option<bool>,
+    usd_pegged: Option<bool>,
+    prices: Option<[u64; PHASE_COUNT]>,
+) -> Result<()> {
+    let payment_mint_config = &mut ctx.accounts.payment_mint_config;
+    
+    if let Some(enabled) = is_enabled {
+        payment_mint_config.is_enabled = enabled;
+    }
+    
+    if let Some(pegged) = usd_pegged {
+        payment_mint_config.usd_pegged = pegged;
+    }
+    
+    if let Some(prices) = prices {
+        payment_mint_config.prices = prices;
+    }
+    
+    if !payment_mint_config.usd_pegged {
+        require!(
+            payment_mint_config.prices.iter().all(|price| *price > 0),
+            PresaleError::InvalidPhaseConfig
+        );
+    }
+    
+    msg!("Payment mint {} updated", payment_mint_config.mint);
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::PaymentMint {
+            mint: payment_mint_config.mint,
+            is_enabled: payment_mint_config.is_enabled,
+            usd_pegged: payment_mint_config.usd_pegged,
+            prices: payment_mint_config.prices,
+        },
+    });
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::events::{ConfigSection, ConfigUpdatedEvent};
+
+#[derive(Accounts)]
+pub struct SetSolPriceFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+}
+
+pub fn handler(
+    ctx: Context<SetSolPriceFeed>,
+    price_feed: Option<Pubkey>,
+    max_price_age: i64,
+    max_confidence_bps: u16,
+) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    
+    require!(max_price_age > 0, PresaleError::InvalidPriceFeed);
+    require!(max_confidence_bps <= 10_000, PresaleError::InvalidPriceFeed);
+    
+    // `None` switches SOL purchases back to the fixed per-phase `price_sol`
+    presale_config.sol_price_feed = price_feed.unwrap_or_default();
+    presale_config.max_price_age = max_price_age;
+    presale_config.max_confidence_bps = max_confidence_bps;
+    
+    msg!("SOL price feed set to {:?}", price_feed);
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::SolPriceFeed {
+            price_feed,
+            max_price_age,
+            max_confidence_bps,
+        },
+    });
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::events::{ConfigSection, ConfigUpdatedEvent};
+
+#[derive(Accounts)]
+#[instruction(code_hash: [u8; 32])]
+pub struct CreatePromoCode<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = PromoCode::SIZE,
+        seeds = [PROMO_CODE_SEED, code_hash.as_ref()],
+        bump
+    )]
+    pub promo_code: Account<'info, PromoCode>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePromoCode<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [PROMO_CODE_SEED, promo_code.code_hash.as_ref()],
+        bump = promo_code.bump
+    )]
+    pub promo_code: Account<'info, PromoCode>,
+}
+
+pub fn handler(
+    ctx: Context<CreatePromoCode>,
+    code_hash: [u8; 32],
+    discount_bps: u16,
+    max_uses: u32,
+    expires_at: i64,
+    phase: Option<u8>,
+) -> Result<()> {
+    require!(
+        discount_bps > 0 && discount_bps < 10_000 && max_uses > 0,
+        PresaleError::InvalidPhaseConfig
+    );
+    if let Some(phase) = phase {
+        require!((phase as usize) < PHASE_COUNT, PresaleError::InvalidPhaseConfig);
+    }
+    
+    let promo_code = &mut ctx.accounts.promo_code;
+    promo_code.code_hash = code_hash;
+    promo_code.discount_bps = discount_bps;
+    promo_code.max_uses = max_uses;
+    promo_code.uses = 0;
+    promo_code.expires_at = expires_at;
+    promo_code.phase = phase;
+    promo_code.is_active = true;
+    promo_code.bump = ctx.bumps.promo_code;
+    
+    msg!("Promo code created: {} bps, {} uses", discount_bps, max_uses);
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::PromoCode {
+            code_hash: promo_code.code_hash,
+            discount_bps: promo_code.discount_bps,
+            max_uses: promo_code.max_uses,
+            expires_at: promo_code.expires_at,
+            phase: promo_code.phase,
+            is_active: promo_code.is_active,
+        },
+    });
+    Ok(())
+}
+
+pub fn handler_update(
+    ctx: Context<UpdatePromoCode>,
+    max_uses: Option<u32>,
+    expires_at: Option<i64>,
+    is_active: Option<bool>,
+) -> Result<()> {
+    let promo_code = &mut ctx.accounts.promo_code;
+    
+    if let Some(max_uses) = max_uses {
+        promo_code.max_uses = max_uses;
+    }
+    
+    if let Some(expires_at) = expires_at {
+        promo_code.expires_at = expires_at;
+    }
+    
+    if let Some(is_active) = is_active {
+        promo_code.is_active = is_active;
+    }
+    
+    msg!("Promo code updated");
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::PromoCode {
+            code_hash: promo_code.code_hash,
+            discount_bps: promo_code.discount_bps,
+            max_uses: promo_code.max_uses,
+            expires_at: promo_code.expires_at,
+            phase: promo_code.phase,
+            is_active: promo_code.is_active,
+        },
+    });
+    Ok(())
+}
@@ -1,9 +1,12 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer as SystemTransfer};
-use anchor_spl::token::{Token, Transfer};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
 use crate::state::*;
+use crate::token_fees;
 use crate::constants::*;
 use crate::error::PresaleError;
+use crate::oracle;
+use crate::events::{PurchaseEvent, SaleRound};
 
 #[derive(Accounts)]
 pub struct Purchase<'info> {
@@ -34,63 +37,365 @@ pub struct Purchase<'info> {
     )]
     pub sol_vault: SystemAccount<'info>,
     
+    /// Registry entry, required when paying with a mint other than SOL / USDC
     #[account(
         mut,
-        seeds = [USDC_VAULT_SEED],
-        bump
+        seeds = [PAYMENT_MINT_SEED, payment_mint_config.mint.as_ref()],
+        bump = payment_mint_config.bump
     )]
-    /// CHECK: Validated in handler
-    pub usdc_vault: UncheckedAccount<'info>,
+    pub payment_mint_config: Option<Account<'info, PaymentMintConfig>>,
+    
+    /// Payment token mint, required for SPL / Token-2022 payments
+    pub payment_token_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// CHECK: SOL/USD feed, required for SOL payments when oracle pricing is on; validated in handler
+    pub sol_price_feed: Option<UncheckedAccount<'info>>,
     
+    /// Admin-pushed SOL/USD rate, used when no price feed is usable
+    #[account(
+        seeds = [EXCHANGE_RATE_SEED],
+        bump = exchange_rate.bump
+    )]
+    pub exchange_rate: Option<Account<'info, ExchangeRate>>,
+    
+    /// CHECK: USDC vault or the registry entry's vault, validated in handler
     #[account(mut)]
+    pub payment_vault: Option<UncheckedAccount<'info>>,
+    
     /// CHECK: Validated in handler
-    pub buyer_usdc_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub buyer_payment_account: Option<UncheckedAccount<'info>>,
+    
+    /// Registered referrer, required when a referrer is named
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+    
+    /// Promo code entry, required when a promo code is given
+    #[account(
+        mut,
+        seeds = [PROMO_CODE_SEED, promo_code.code_hash.as_ref()],
+        bump = promo_code.bump
+    )]
+    pub promo_code: Option<Account<'info, PromoCode>>,
+    
+    /// Receipt for this purchase, indexed by the buyer's receipt count
+    #[account(
+        init,
+        payer = buyer,
+        space = PurchaseReceipt::SIZE,
+        seeds = [
+            PURCHASE_RECEIPT_SEED,
+            buyer.key().as_ref(),
+            user_purchase.receipt_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub receipt: Option<Account<'info, PurchaseReceipt>>,
     
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+/// Returned to the caller so clients can see how the order was priced.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PurchaseResult {
+    pub token_amount: u64,
+    pub payment_amount: u64,
+    pub discount_bps: u16, // volume tier plus promo code
+    pub discount_amount: u64, // saved against the same fills at full price
+    pub bonus_tokens: u64, // phase bonus from the bonus pool
+    pub referral_bonus: u64, // vested tokens added for the buyer
+    pub fills: Vec<PhaseFill>,
+}
+
+/// How the buyer sized the order.
+#[derive(Clone, Copy)]
+enum OrderSize {
+    /// Exact number of sale tokens
+    Tokens(u64),
+    /// Exact amount of payment base units to spend
+    Spend(u64),
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<Purchase>,
     token_amount: u64,
-    payment_type: PaymentType,
-) -> Result<()> {
+    payment_mint: Pubkey,
+    max_payment: Option<u64>,
+    expected_phase: Option<u8>,
+    deadline: Option<i64>,
+    referrer: Option<Pubkey>,
+    promo_code: Option<String>,
+    client_order_id: Option<[u8; 32]>,
+) -> Result<PurchaseResult> {
+    process_purchase(
+        ctx,
+        OrderSize::Tokens(token_amount),
+        payment_mint,
+        max_payment,
+        None,
+        expected_phase,
+        deadline,
+        referrer,
+        promo_code,
+        client_order_id,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler_exact_spend(
+    ctx: Context<Purchase>,
+    spend_amount: u64,
+    payment_mint: Pubkey,
+    min_tokens: Option<u64>,
+    expected_phase: Option<u8>,
+    deadline: Option<i64>,
+    referrer: Option<Pubkey>,
+    promo_code: Option<String>,
+    client_order_id: Option<[u8; 32]>,
+) -> Result<PurchaseResult> {
+    process_purchase(
+        ctx,
+        OrderSize::Spend(spend_amount),
+        payment_mint,
+        Some(spend_amount),
+        min_tokens,
+        expected_phase,
+        deadline,
+        referrer,
+        promo_code,
+        client_order_id,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_purchase(
+    ctx: Context<Purchase>,
+    order: OrderSize,
+    payment_mint: Pubkey,
+    max_payment: Option<u64>,
+    min_tokens: Option<u64>,
+    expected_phase: Option<u8>,
+    deadline: Option<i64>,
+    referrer: Option<Pubkey>,
+    promo_code: Option<String>,
+    client_order_id: Option<[u8; 32]>,
+) -> Result<PurchaseResult> {
     let presale_config = &mut ctx.accounts.presale_config;
     let user_purchase = &mut ctx.accounts.user_purchase;
     let clock = Clock::get()?;
     
-    // Validate unchecked accounts
-    let buyer_usdc_account_data = anchor_spl::token::TokenAccount::try_deserialize(&mut &ctx.accounts.buyer_usdc_account.data.borrow()[..])?;
-    require!(
-        buyer_usdc_account_data.owner == ctx.accounts.buyer.key(),
-        PresaleError::Unauthorized
-    );
-    require!(
-        buyer_usdc_account_data.mint == presale_config.usdc_mint,
-        PresaleError::InvalidTokenMint
-    );
+    // Resolve the payment method and validate unchecked accounts
+    let payment_type = presale_config.payment_type_for(&payment_mint);
+    let expected_vault = match payment_type {
+        PaymentType::Sol => None,
+        PaymentType::Usdc => Some(presale_config.usdc_vault),
+        PaymentType::Spl => {
+            let entry = ctx
+                .accounts
+                .payment_mint_config
+                .as_ref()
+                .ok_or(PresaleError::InvalidPaymentType)?;
+            require!(
+                entry.mint == payment_mint && entry.is_enabled,
+                PresaleError::InvalidPaymentType
+            );
+            Some(entry.vault)
+        }
+    };
+    
+    if let Some(expected_vault) = expected_vault {
+        let payment_token_mint = ctx
+            .accounts
+            .payment_token_mint
+            .as_ref()
+            .ok_or(PresaleError::InvalidTokenMint)?;
+        require!(
+            payment_token_mint.key() == payment_mint,
+            PresaleError::InvalidTokenMint
+        );
+        
+        let payment_vault = ctx
+            .accounts
+            .payment_vault
+            .as_ref()
+            .ok_or(PresaleError::InvalidPaymentVault)?;
+        require!(
+            payment_vault.key() == expected_vault,
+            PresaleError::InvalidPaymentVault
+        );
+        
+        let buyer_payment_account = ctx
+            .accounts
+            .buyer_payment_account
+            .as_ref()
+            .ok_or(PresaleError::InvalidTokenMint)?;
+        let buyer_payment_account_data = TokenAccount::try_deserialize(&mut &buyer_payment_account.data.borrow()[..])?;
+        require!(
+            buyer_payment_account_data.owner == ctx.accounts.buyer.key(),
+            PresaleError::Unauthorized
+        );
+        require!(
+            buyer_payment_account_data.mint == payment_mint,
+            PresaleError::InvalidTokenMint
+        );
+    }
+    
+    // A named referrer must be registered, active and not the buyer
+    if let Some(referrer) = referrer {
+        let referrer_account = ctx
+            .accounts
+            .referrer
+            .as_ref()
+            .ok_or(PresaleError::InvalidReferrer)?;
+        require!(
+            referrer_account.wallet == referrer
+                && referrer_account.is_active
+                && referrer != ctx.accounts.buyer.key(),
+            PresaleError::InvalidReferrer
+        );
+    }
     
     // Check if presale is paused
     require!(!presale_config.is_paused, PresaleError::PresalePaused);
     
+    // Reject transactions that landed after the buyer's deadline
+    if let Some(deadline) = deadline {
+        require!(clock.unix_timestamp <= deadline, PresaleError::DeadlineExceeded);
+    }
+    
+    // Apply rollover / carry-over before resolving the active phase
+    presale_config.sync_phases(clock.unix_timestamp)?;
+    
     // Get current active phase
     let phase_index = presale_config
         .get_current_phase(clock.unix_timestamp)
         .ok_or(PresaleError::NoActivePhase)?;
     
-    // Extract all needed values from presale_config before borrowing phase to avoid borrow conflicts
+    if let Some(expected_phase) = expected_phase {
+        require!(
+            phase_index == expected_phase as usize,
+            PresaleError::UnexpectedPhase
+        );
+    }
+    
+    // Promo codes are checked against the phase the order starts in
+    let promo_discount_bps = match promo_code.as_deref() {
+        Some(code) => {
+            let entry = ctx
+                .accounts
+                .promo_code
+                .as_ref()
+                .ok_or(PresaleError::InvalidPromoCode)?;
+            entry.validate(code, phase_index, clock.unix_timestamp)?;
+            entry.discount_bps
+        }
+        None => 0,
+    };
+    
+    // Extract all needed values from presale_config before mutating phases to avoid borrow conflicts
     let launch_timestamp = presale_config.launch_timestamp;
     let max_purchase_per_transaction = presale_config.max_purchase_per_transaction;
     let max_purchase_per_wallet = presale_config.max_purchase_per_wallet;
     let min_time_between_purchases = presale_config.min_time_between_purchases;
-    let price_per_token = presale_config
-        .get_phase_price(phase_index, payment_type)
-        .ok_or(PresaleError::InvalidPhaseConfig)?;
     let vesting_launch_percentage = presale_config.vesting_launch_percentage;
     let vesting_monthly_percentage = presale_config.vesting_monthly_percentage;
     
-    let phase = &mut presale_config.phases[phase_index];
+    // Check phase availability and price the order, spilling into later
+    // phases when rollover is enabled
+    // Phases are priced in USD when a SOL/USD feed or exchange rate is configured
+    let sol_usd = if payment_type == PaymentType::Sol {
+        sol_usd_price(
+            presale_config,
+            ctx.accounts.sol_price_feed.as_ref(),
+            ctx.accounts.exchange_rate.as_deref(),
+            clock.unix_timestamp,
+        )?
+    } else {
+        None
+    };
+    
+    let payment_mint_config = ctx.accounts.payment_mint_config.as_deref();
+    let price_of = |index: usize, discount_bps: u16| {
+        presale_config
+            .payment_price(index, payment_type, payment_mint_config, sol_usd.as_ref())?
+            .map(|price| price.discount(discount_bps))
+    };
+    // Volume and promo discounts on the whole order
+    let discount_for = |token_amount: u64| {
+        presale_config
+            .discount_bps_for(token_amount)
+            .saturating_add(promo_discount_bps)
+            .min(10_000)
+    };
+    
+    // Orders are planned at the discounted prices, so a spend-exact order
+    // spends the requested amount and receives more tokens
+    let (fills, discount_bps) = match order {
+        OrderSize::Tokens(token_amount) => {
+            let discount_bps = discount_for(token_amount);
+            let fills = presale_config.plan_purchase(
+                phase_index,
+                token_amount,
+                clock.unix_timestamp,
+                |index| price_of(index, discount_bps),
+            )?;
+            (fills, discount_bps)
+        }
+        OrderSize::Spend(spend_amount) => {
+            // Leave room for a Token-2022 transfer fee inside the spend
+            let spend_amount = match ctx.accounts.payment_token_mint.as_ref() {
+                Some(mint) if payment_type != PaymentType::Sol => {
+                    let fee = token_fees::transfer_fee(&mint.to_account_info(), spend_amount)?;
+                    spend_amount.saturating_sub(fee)
+                }
+                _ => spend_amount,
+            };
+            let max_tokens = presale_config.spend_token_cap(user_purchase);
+            
+            // A deeper discount buys more tokens, which can reach a higher
+            // tier. The discount only grows, so this settles within the tier count
+            let mut discount_bps = discount_for(0);
+            loop {
+                let fills = presale_config.plan_spend(
+                    phase_index,
+                    spend_amount,
+                    max_tokens,
+                    clock.unix_timestamp,
+                    |index| price_of(index, discount_bps),
+                )?;
+                let planned_tokens = fills.iter().map(|fill| fill.token_amount).sum();
+                let next_discount_bps = discount_for(planned_tokens);
+                if next_discount_bps == discount_bps {
+                    break (fills, discount_bps);
+                }
+                discount_bps = next_discount_bps;
+            }
+        }
+    };
+    
+    let mut token_amount: u64 = 0;
+    let mut payment_amount: u64 = 0;
+    for fill in &fills {
+        token_amount = token_amount
+            .checked_add(fill.token_amount)
+            .ok_or(PresaleError::MathOverflow)?;
+        payment_amount = payment_amount
+            .checked_add(fill.payment_amount)
+            .ok_or(PresaleError::MathOverflow)?;
+    }
+    
+    // Phases cannot sell supply reserved for other rounds
+    require!(
+        token_amount <= presale_config.unsold_supply(),
+        PresaleError::InsufficientTokensInPhase
+    );
     
     // Validate token amount
     require!(
@@ -121,32 +426,44 @@ pub fn handler(
         );
     }
     
-    // Check phase availability
-    let remaining_in_phase = phase
-        .tokens_allocated
-        .checked_sub(phase.tokens_sold)
-        .ok_or(PresaleError::MathOverflow)?;
+    require!(payment_amount > 0, PresaleError::InsufficientPayment);
     
-    require!(
-        token_amount <= remaining_in_phase,
-        PresaleError::InsufficientTokensInPhase
-    );
+    if let Some(min_tokens) = min_tokens {
+        require!(token_amount >= min_tokens, PresaleError::MinTokensNotMet);
+    }
     
-    // Calculate payment amount
-    // Price is already in the correct units (lamports for SOL, micro-USDC for USDC)
-    // token_amount is in the token's native units (assuming 9 decimals)
+    // Reported saving against the same fills at full price
+    let discount_amount = if discount_bps > 0 {
+        let full_price_fills = presale_config.plan_purchase(
+            phase_index,
+            token_amount,
+            clock.unix_timestamp,
+            |index| price_of(index, 0),
+        )?;
+        full_price_fills
+            .iter()
+            .map(|fill| fill.payment_amount)
+            .sum::<u64>()
+            .saturating_sub(payment_amount)
+    } else {
+        0
+    };
     
-    // Calculate total payment: price_per_token * token_amount
-    // Both are in their native units, so we multiply directly
-    let payment_amount = price_per_token
-        .checked_mul(token_amount)
-        .and_then(|p| p.checked_div(1_000_000_000)) // Convert from token units (9 decimals) to payment units
-        .ok_or(PresaleError::MathOverflow)?;
+    // Transfer-fee mints are grossed up so the vault nets the full price
+    let charge_amount = match ctx.accounts.payment_token_mint.as_ref() {
+        Some(mint) if payment_type != PaymentType::Sol => {
+            token_fees::gross_up_for_fee(&mint.to_account_info(), payment_amount)?
+        }
+        _ => payment_amount,
+    };
     
-    require!(payment_amount > 0, PresaleError::InsufficientPayment);
+    // Slippage protection against phase boundaries and rollover
+    if let Some(max_payment) = max_payment {
+        require!(charge_amount <= max_payment, PresaleError::SlippageExceeded);
+    }
     
-    // Process payment
-    match payment_type {
+    // Process payment, recording what actually arrived in the vault
+    let received_amount = match payment_type {
         PaymentType::Sol => {
             // Transfer SOL using system program
             let cpi_accounts = SystemTransfer {
@@ -156,19 +473,35 @@ pub fn handler(
             let cpi_program = ctx.accounts.system_program.to_account_info();
             let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
             transfer(cpi_ctx, payment_amount)?;
+            payment_amount
         }
-        PaymentType::Usdc => {
-            // Transfer USDC
-            let cpi_accounts = Transfer {
-                from: ctx.accounts.buyer_usdc_account.to_account_info(),
-                to: ctx.accounts.usdc_vault.to_account_info(),
+        PaymentType::Usdc | PaymentType::Spl => {
+            // Transfer USDC or a registered SPL token (accounts validated above)
+            let payment_token_mint = ctx.accounts.payment_token_mint.as_ref().unwrap();
+            let payment_vault = ctx.accounts.payment_vault.as_ref().unwrap().to_account_info();
+            let vault_balance_before = token_fees::token_balance(&payment_vault)?;
+            
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.buyer_payment_account.as_ref().unwrap().to_account_info(),
+                mint: payment_token_mint.to_account_info(),
+                to: payment_vault.clone(),
                 authority: ctx.accounts.buyer.to_account_info(),
             };
             let cpi_program = ctx.accounts.token_program.to_account_info();
             let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-            anchor_spl::token::transfer(cpi_ctx, payment_amount)?;
+            anchor_spl::token_interface::transfer_checked(
+                cpi_ctx,
+                charge_amount,
+                payment_token_mint.decimals,
+            )?;
+            
+            let received = token_fees::token_balance(&payment_vault)?
+                .checked_sub(vault_balance_before)
+                .ok_or(PresaleError::MathOverflow)?;
+            require!(received >= payment_amount, PresaleError::InsufficientPayment);
+            received
         }
-    }
+    };
     
     // Update user purchase record
     if user_purchase.wallet == Pubkey::default() {
@@ -176,6 +509,11 @@ pub fn handler(
         user_purchase.bump = ctx.bumps.user_purchase;
     }
     
+    // Sale statistics
+    presale_config.stats.record_buyer(user_purchase.total_purchased, token_amount)?;
+    presale_config.stats.record_raised(payment_type, received_amount)?;
+    presale_config.stats.record_phase_raised(payment_type, &fills, received_amount)?;
+    
     user_purchase.total_purchased = new_total;
     user_purchase.last_purchase_time = clock.unix_timestamp;
     
@@ -183,83 +521,236 @@ pub fn handler(
         PaymentType::Sol => {
             user_purchase.total_spent_sol = user_purchase
                 .total_spent_sol
-                .checked_add(payment_amount)
+                .checked_add(received_amount)
                 .ok_or(PresaleError::MathOverflow)?;
         }
         PaymentType::Usdc => {
             user_purchase.total_spent_usdc = user_purchase
                 .total_spent_usdc
-                .checked_add(payment_amount)
+                .checked_add(received_amount)
+                .ok_or(PresaleError::MathOverflow)?;
+        }
+        PaymentType::Spl => {
+            let entry = ctx.accounts.payment_mint_config.as_mut().unwrap();
+            entry.total_received = entry
+                .total_received
+                .checked_add(received_amount)
                 .ok_or(PresaleError::MathOverflow)?;
         }
     }
     
     // Calculate and set up vesting schedule
-    let launch_amount = token_amount
-        .checked_mul(vesting_launch_percentage as u64)
-        .and_then(|a| a.checked_div(100))
-        .ok_or(PresaleError::MathOverflow)?;
-    
-    let monthly_amount = token_amount
-        .checked_mul(vesting_monthly_percentage as u64)
-        .and_then(|a| a.checked_div(100))
-        .ok_or(PresaleError::MathOverflow)?;
+    user_purchase.add_vesting(
+        token_amount,
+        launch_timestamp,
+        vesting_launch_percentage,
+        vesting_monthly_percentage,
+    )?;
+    
+    // Count the promo code use
+    if promo_code.is_some() {
+        let entry = ctx.accounts.promo_code.as_mut().unwrap();
+        entry.uses = entry.uses.checked_add(1).ok_or(PresaleError::MathOverflow)?;
+    }
     
-    // Update vesting schedule
-    // Launch vesting (40% at launch)
-    let launch_release_time = launch_timestamp;
-    if user_purchase.vesting_schedule[0].release_time == 0 {
-        user_purchase.vesting_schedule[0] = VestingEntry {
-            amount: launch_amount,
-            release_time: launch_release_time,
-            claimed: false,
-        };
-    } else {
-        user_purchase.vesting_schedule[0].amount = user_purchase.vesting_schedule[0]
-            .amount
-            .checked_add(launch_amount)
+    // Phase bonus tokens come from the bonus pool and follow its own schedule
+    let bonus_tokens = presale_config.bonus_for(&fills)?;
+    if bonus_tokens > 0 {
+        user_purchase.add_vesting(
+            bonus_tokens,
+            launch_timestamp,
+            presale_config.bonus_vesting_launch_percentage,
+            presale_config.bonus_vesting_monthly_percentage,
+        )?;
+        presale_config.bonus_tokens_granted = presale_config
+            .bonus_tokens_granted
+            .checked_add(bonus_tokens)
             .ok_or(PresaleError::MathOverflow)?;
     }
     
-    // Monthly vesting (30% each month, 30 days = 2,592,000 seconds)
-    let monthly_interval: i64 = 2_592_000;
-    for i in 1..=VESTING_MONTHS as usize {
-        let release_time = launch_timestamp
-            .checked_add((i as i64).checked_mul(monthly_interval).ok_or(PresaleError::MathOverflow)?)
-            .ok_or(PresaleError::MathOverflow)?;
+    // Book the referral reward
+    let mut referral_bonus: u64 = 0;
+    let mut referral_tokens: u64 = 0;
+    if referrer.is_some() {
+        let referrer_account = ctx.accounts.referrer.as_mut().unwrap();
+        let reward_bps = presale_config.referral_reward_bps as u128;
+        // Token bonuses come out of the referral pool
+        let available = presale_config
+            .referral_pool
+            .saturating_sub(presale_config.referral_tokens_booked);
+        let token_bonus = ((token_amount as u128 * reward_bps / 10_000) as u64).min(available);
         
-        if user_purchase.vesting_schedule[i].release_time == 0 {
-            user_purchase.vesting_schedule[i] = VestingEntry {
-                amount: monthly_amount,
-                release_time: release_time,
-                claimed: false,
-            };
-        } else {
-            user_purchase.vesting_schedule[i].amount = user_purchase.vesting_schedule[i]
-                .amount
-                .checked_add(monthly_amount)
-                .ok_or(PresaleError::MathOverflow)?;
+        match presale_config.referral_reward {
+            ReferralReward::None => {}
+            ReferralReward::BuyerTokens => {
+                user_purchase.add_vesting(
+                    token_bonus,
+                    launch_timestamp,
+                    vesting_launch_percentage,
+                    vesting_monthly_percentage,
+                )?;
+                referral_bonus = token_bonus;
+                referral_tokens = token_bonus;
+            }
+            ReferralReward::ReferrerTokens => {
+                referrer_account.pending_tokens = referrer_account
+                    .pending_tokens
+                    .checked_add(token_bonus)
+                    .ok_or(PresaleError::MathOverflow)?;
+                referral_tokens = token_bonus;
+            }
+            ReferralReward::Commission => {
+                // Commissions are paid out in SOL or USDC only
+                let commission = (received_amount as u128 * reward_bps / 10_000) as u64;
+                match payment_type {
+                    PaymentType::Sol => {
+                        referrer_account.pending_sol = referrer_account
+                            .pending_sol
+                            .checked_add(commission)
+                            .ok_or(PresaleError::MathOverflow)?;
+                    }
+                    PaymentType::Usdc => {
+                        referrer_account.pending_usdc = referrer_account
+                            .pending_usdc
+                            .checked_add(commission)
+                            .ok_or(PresaleError::MathOverflow)?;
+                    }
+                    PaymentType::Spl => return err!(PresaleError::CommissionNotSupported),
+                }
+            }
         }
+        
+        referrer_account.referred_purchases = referrer_account
+            .referred_purchases
+            .checked_add(1)
+            .ok_or(PresaleError::MathOverflow)?;
+        referrer_account.referred_tokens = referrer_account
+            .referred_tokens
+            .checked_add(token_amount)
+            .ok_or(PresaleError::MathOverflow)?;
+        presale_config.referral_tokens_booked = presale_config
+            .referral_tokens_booked
+            .checked_add(referral_tokens)
+            .ok_or(PresaleError::MathOverflow)?;
     }
     
     // Update presale config
-    phase.tokens_sold = phase
-        .tokens_sold
-        .checked_add(token_amount)
-        .ok_or(PresaleError::MathOverflow)?;
+    for fill in &fills {
+        let phase = &mut presale_config.phases[fill.phase as usize];
+        phase.tokens_sold = phase
+            .tokens_sold
+            .checked_add(fill.token_amount)
+            .ok_or(PresaleError::MathOverflow)?;
+    }
     
     presale_config.tokens_sold = presale_config
         .tokens_sold
         .checked_add(token_amount)
         .ok_or(PresaleError::MathOverflow)?;
     
+    // Close the phase right away if this purchase sold it out
+    presale_config.sync_phases(clock.unix_timestamp)?;
+    
+    // Optional receipt, stored under the buyer's next receipt index
+    if let Some(receipt) = ctx.accounts.receipt.as_mut() {
+        receipt.buyer = ctx.accounts.buyer.key();
+        receipt.index = user_purchase.receipt_count;
+        receipt.timestamp = clock.unix_timestamp;
+        receipt.phase = phase_index as u8;
+        receipt.phases_filled = fills.len() as u8;
+        receipt.price = fills.first().map_or(0, |fill| fill.price);
+        receipt.payment_type = payment_type;
+        receipt.payment_mint = payment_mint;
+        receipt.amount_paid = received_amount;
+        receipt.tokens_received = token_amount;
+        receipt.bonus_tokens = bonus_tokens
+            .checked_add(referral_bonus)
+            .ok_or(PresaleError::MathOverflow)?;
+        receipt.client_order_id = client_order_id;
+        receipt.bump = ctx.bumps.receipt.unwrap();
+        user_purchase.receipt_count = user_purchase
+            .receipt_count
+            .checked_add(1)
+            .ok_or(PresaleError::MathOverflow)?;
+    }
+    
     msg!(
-        "Purchase: {} tokens for {} (payment type: {:?})",
+        "Purchase: {} tokens for {} (payment type: {:?}, mint: {}, phases filled: {}, discount: {} bps, phase bonus: {}, referral bonus: {})",
         token_amount,
-        payment_amount,
-        payment_type
+        received_amount,
+        payment_type,
+        payment_mint,
+        fills.len(),
+        discount_bps,
+        bonus_tokens,
+        referral_bonus
     );
-    
-    Ok(())
+    emit!(PurchaseEvent {
+        buyer: ctx.accounts.buyer.key(),
+        round: SaleRound::Phases,
+        phase: phase_index as u8,
+        phases_filled: fills.len() as u8,
+        token_amount,
+        payment_amount: received_amount,
+        payment_type,
+        payment_mint,
+        price: fills.first().map_or(0, |fill| fill.price),
+        discount_bps,
+        bonus_tokens,
+        referrer,
+    });
+    
+    Ok(PurchaseResult {
+        token_amount,
+        payment_amount: received_amount,
+        discount_bps,
+        discount_amount,
+        bonus_tokens,
+        referral_bonus,
+        fills,
+    })
 }
 
+/// Resolves the SOL/USD rate: the price feed first, then the exchange-rate
+/// account as a fallback. `None` means SOL uses the fixed per-phase price.
+fn sol_usd_price(
+    presale_config: &PresaleConfig,
+    sol_price_feed: Option<&UncheckedAccount>,
+    exchange_rate: Option<&ExchangeRate>,
+    current_time: i64,
+) -> Result<Option<oracle::OraclePrice>> {
+    let from_exchange_rate = || -> Result<oracle::OraclePrice> {
+        exchange_rate
+            .ok_or(PresaleError::InvalidExchangeRate)?
+            .validate(current_time)
+    };
+    
+    if presale_config.uses_sol_price_feed() {
+        let from_feed = || -> Result<oracle::OraclePrice> {
+            let feed = sol_price_feed.ok_or(PresaleError::InvalidPriceFeed)?;
+            require!(
+                feed.key() == presale_config.sol_price_feed,
+                PresaleError::InvalidPriceFeed
+            );
+            let sol_usd = oracle::read_price_feed(feed)?;
+            sol_usd.validate(
+                current_time,
+                presale_config.max_price_age,
+                presale_config.max_confidence_bps,
+            )?;
+            Ok(sol_usd)
+        };
+        
+        return match from_feed() {
+            Ok(sol_usd) => Ok(Some(sol_usd)),
+            Err(_) if presale_config.use_exchange_rate => from_exchange_rate().map(Some),
+            Err(err) => Err(err),
+        };
+    }
+    
+    if presale_config.use_exchange_rate {
+        return from_exchange_rate().map(Some);
+    }
+    
+    Ok(None)
+}
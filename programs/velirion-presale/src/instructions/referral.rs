@@ -0,0 +1,205 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenInterface};
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::payouts;
+use crate::events::{ClaimEvent, ClaimSource};
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RegisterReferrer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = Referrer::SIZE,
+        seeds = [REFERRER_SEED, wallet.as_ref()],
+        bump
+    )]
+    pub referrer: Account<'info, Referrer>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetReferrerActive<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump
+    )]
+    pub referrer: Account<'info, Referrer>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    
+    #[account(
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, wallet.key().as_ref()],
+        bump = referrer.bump
+    )]
+    pub referrer: Account<'info, Referrer>,
+    
+    /// Token rewards vest like purchased tokens
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = UserPurchase::SIZE,
+        seeds = [USER_PURCHASE_SEED, wallet.key().as_ref()],
+        bump
+    )]
+    pub user_purchase: Account<'info, UserPurchase>,
+    
+    /// CHECK: SOL vault that pays out commissions
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    
+    /// USDC mint, required to pay USDC commissions
+    #[account(address = presale_config.usdc_mint @ PresaleError::InvalidTokenMint)]
+    pub usdc_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// CHECK: Must be the presale's USDC vault
+    #[account(mut, address = presale_config.usdc_vault @ PresaleError::InvalidPaymentVault)]
+    pub usdc_vault: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Referrer's USDC account; the token program checks the mint
+    #[account(mut)]
+    pub referrer_usdc_account: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn handler(ctx: Context<RegisterReferrer>, wallet: Pubkey) -> Result<()> {
+    let referrer = &mut ctx.accounts.referrer;
+    referrer.wallet = wallet;
+    referrer.is_active = true;
+    referrer.referred_purchases = 0;
+    referrer.referred_tokens = 0;
+    referrer.pending_tokens = 0;
+    referrer.pending_sol = 0;
+    referrer.pending_usdc = 0;
+    referrer.bump = ctx.bumps.referrer;
+    
+    msg!("Referrer {} registered", wallet);
+    Ok(())
+}
+
+pub fn handler_set_active(ctx: Context<SetReferrerActive>, is_active: bool) -> Result<()> {
+    let referrer = &mut ctx.accounts.referrer;
+    referrer.is_active = is_active;
+    
+    msg!("Referrer {} active: {}", referrer.wallet, is_active);
+    Ok(())
+}
+
+pub fn handler_claim(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+    let presale_config = &ctx.accounts.presale_config;
+    let referrer = &mut ctx.accounts.referrer;
+    
+    let pending_tokens = referrer.pending_tokens;
+    let pending_sol = referrer.pending_sol;
+    let pending_usdc = referrer.pending_usdc;
+    require!(
+        pending_tokens > 0 || pending_sol > 0 || pending_usdc > 0,
+        PresaleError::NoReferralRewards
+    );
+    referrer.pending_tokens = 0;
+    referrer.pending_sol = 0;
+    referrer.pending_usdc = 0;
+    
+    if pending_tokens > 0 {
+        let user_purchase = &mut ctx.accounts.user_purchase;
+        if user_purchase.wallet == Pubkey::default() {
+            user_purchase.wallet = ctx.accounts.wallet.key();
+            user_purchase.bump = ctx.bumps.user_purchase;
+        }
+        user_purchase.add_vesting(
+            pending_tokens,
+            presale_config.launch_timestamp,
+            presale_config.vesting_launch_percentage,
+            presale_config.vesting_monthly_percentage,
+        )?;
+    }
+    
+    if pending_sol > 0 {
+        payouts::pay_from_sol_vault(
+            &ctx.accounts.sol_vault.to_account_info(),
+            &ctx.accounts.wallet.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.bumps.sol_vault,
+            pending_sol,
+        )?;
+    }
+    
+    if pending_usdc > 0 {
+        let usdc_mint = ctx.accounts.usdc_mint.as_ref().ok_or(PresaleError::InvalidTokenMint)?;
+        let usdc_vault = ctx
+            .accounts
+            .usdc_vault
+            .as_ref()
+            .ok_or(PresaleError::InvalidPaymentVault)?;
+        let referrer_usdc_account = ctx
+            .accounts
+            .referrer_usdc_account
+            .as_ref()
+            .ok_or(PresaleError::InvalidTokenMint)?;
+        payouts::pay_from_token_vault(
+            &usdc_vault.to_account_info(),
+            usdc_mint,
+            &referrer_usdc_account.to_account_info(),
+            &presale_config.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            presale_config.bump,
+            pending_usdc,
+        )?;
+    }
+    
+    msg!(
+        "Referral rewards claimed: {} tokens vested, {} lamports, {} USDC",
+        pending_tokens,
+        pending_sol,
+        pending_usdc
+    );
+    emit!(ClaimEvent {
+        wallet: ctx.accounts.wallet.key(),
+        source: ClaimSource::Referral,
+        amount: pending_tokens,
+        received_amount: 0,
+        sol_paid: pending_sol,
+        usdc_paid: pending_usdc,
+    });
+    Ok(())
+}
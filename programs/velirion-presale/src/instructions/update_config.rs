@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
 use crate::state::*;
 use crate::constants::*;
 use crate::error::PresaleError;
+use crate::events::{ConfigSection, ConfigUpdatedEvent};
 
 #[derive(Accounts)]
 pub struct UpdateConfig<'info> {
@@ -17,11 +19,31 @@ pub struct UpdateConfig<'info> {
     pub presale_config: Account<'info, PresaleConfig>,
 }
 
+/// Sizing a token pool paid from the treasury, which must be funded for it.
+#[derive(Accounts)]
+pub struct UpdatePool<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(address = presale_config.treasury @ PresaleError::InvalidTreasury)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+}
+
 pub fn handler(
     ctx: Context<UpdateConfig>,
     max_purchase_per_transaction: Option<u64>,
     max_purchase_per_wallet: Option<u64>,
     min_time_between_purchases: Option<i64>,
+    auto_rollover: Option<bool>,
+    carry_over_unsold: Option<bool>,
+    trim_to_limits: Option<bool>,
 ) -> Result<()> {
     let presale_config = &mut ctx.accounts.presale_config;
     
@@ -38,7 +60,155 @@ pub fn handler(
         presale_config.min_time_between_purchases = min_time;
     }
     
+    if let Some(rollover) = auto_rollover {
+        presale_config.auto_rollover = rollover;
+    }
+    
+    if let Some(carry_over) = carry_over_unsold {
+        presale_config.carry_over_unsold = carry_over;
+    }
+    
+    if let Some(trim) = trim_to_limits {
+        presale_config.trim_to_limits = trim;
+    }
+    
     msg!("Presale config updated");
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::Limits {
+            max_purchase_per_transaction: presale_config.max_purchase_per_transaction,
+            max_purchase_per_wallet: presale_config.max_purchase_per_wallet,
+            min_time_between_purchases: presale_config.min_time_between_purchases,
+            auto_rollover: presale_config.auto_rollover,
+            carry_over_unsold: presale_config.carry_over_unsold,
+            trim_to_limits: presale_config.trim_to_limits,
+        },
+    });
     Ok(())
 }
 
+
+pub fn handler_discount_tiers(ctx: Context<UpdateConfig>, tiers: Vec<DiscountTier>) -> Result<()> {
+    require!(tiers.len() <= MAX_DISCOUNT_TIERS, PresaleError::InvalidPhaseConfig);
+    
+    // Larger orders must never get a smaller discount
+    for (i, tier) in tiers.iter().enumerate() {
+        require!(
+            tier.discount_bps > 0 && tier.discount_bps < 10_000,
+            PresaleError::InvalidPhaseConfig
+        );
+        if i > 0 {
+            require!(
+                tiers[i - 1].min_tokens < tier.min_tokens
+                    && tiers[i - 1].discount_bps < tier.discount_bps,
+                PresaleError::InvalidPhaseConfig
+            );
+        }
+    }
+    
+    let presale_config = &mut ctx.accounts.presale_config;
+    presale_config.discount_tiers = [DiscountTier::default(); MAX_DISCOUNT_TIERS];
+    presale_config.discount_tiers[..tiers.len()].copy_from_slice(&tiers);
+    
+    msg!("Discount tiers updated ({} tiers)", tiers.len());
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::DiscountTiers { tiers },
+    });
+    Ok(())
+}
+
+pub fn handler_referral(
+    ctx: Context<UpdatePool>,
+    reward: ReferralReward,
+    reward_bps: u16,
+    referral_pool: u64,
+) -> Result<()> {
+    require!(reward_bps <= 10_000, PresaleError::InvalidPhaseConfig);
+    
+    let presale_config = &mut ctx.accounts.presale_config;
+    // Referral bonuses already booked cannot be taken back
+    require!(
+        referral_pool >= presale_config.referral_tokens_booked,
+        PresaleError::InvalidPhaseConfig
+    );
+    
+    presale_config.referral_reward = reward;
+    presale_config.referral_reward_bps = reward_bps;
+    presale_config.referral_pool = referral_pool;
+    presale_config.require_treasury_covers(ctx.accounts.treasury.amount)?;
+    
+    msg!(
+        "Referral reward set to {:?} ({} bps, pool of {} tokens)",
+        reward,
+        reward_bps,
+        referral_pool
+    );
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::Referral {
+            reward,
+            reward_bps,
+            referral_pool,
+        },
+    });
+    Ok(())
+}
+
+pub fn handler_bonus_pool(
+    ctx: Context<UpdatePool>,
+    bonus_pool: u64,
+    vesting_launch_percentage: u8,
+    vesting_monthly_percentage: u8,
+) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    
+    require!(
+        vesting_launch_percentage as u16 + vesting_monthly_percentage as u16 * VESTING_MONTHS as u16 == 100,
+        PresaleError::InvalidVestingSchedule
+    );
+    // Bonuses already granted cannot be taken back
+    require!(
+        bonus_pool >= presale_config.bonus_tokens_granted,
+        PresaleError::InvalidPhaseConfig
+    );
+    
+    presale_config.bonus_pool = bonus_pool;
+    presale_config.bonus_vesting_launch_percentage = vesting_launch_percentage;
+    presale_config.bonus_vesting_monthly_percentage = vesting_monthly_percentage;
+    presale_config.require_treasury_covers(ctx.accounts.treasury.amount)?;
+    
+    msg!(
+        "Bonus pool set to {} tokens ({}% at launch, {}% monthly)",
+        bonus_pool,
+        vesting_launch_percentage,
+        vesting_monthly_percentage
+    );
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::BonusPool {
+            bonus_pool,
+            vesting_launch_percentage,
+            vesting_monthly_percentage,
+        },
+    });
+    Ok(())
+}
+
+pub fn handler_reserved_allocation(ctx: Context<UpdatePool>, reserved_allocation: u64) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    
+    require!(
+        reserved_allocation >= presale_config.reserved_tokens_granted,
+        PresaleError::ExceedsReservedAllocation
+    );
+    presale_config.reserved_allocation = reserved_allocation;
+    presale_config.require_treasury_covers(ctx.accounts.treasury.amount)?;
+    
+    msg!("Reserved allocation set to {} tokens", reserved_allocation);
+    emit!(ConfigUpdatedEvent {
+        updated_by: ctx.accounts.authority.key(),
+        section: ConfigSection::ReservedAllocation { reserved_allocation },
+    });
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::error::PresaleError;
+use crate::events::{GrantEvent, GrantKind};
+
+#[derive(Accounts)]
+#[instruction(grant_id: u64)]
+pub struct CreateVestingGrant<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    /// CHECK: Any wallet can receive a grant
+    pub beneficiary: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = VestingGrant::SIZE,
+        seeds = [VESTING_GRANT_SEED, beneficiary.key().as_ref(), grant_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub vesting_grant: Account<'info, VestingGrant>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVestingGrant<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [PRESALE_CONFIG_SEED],
+        bump = presale_config.bump,
+        constraint = presale_config.authority == authority.key() @ PresaleError::Unauthorized
+    )]
+    pub presale_config: Account<'info, PresaleConfig>,
+    
+    #[account(
+        mut,
+        seeds = [
+            VESTING_GRANT_SEED,
+            vesting_grant.beneficiary.as_ref(),
+            vesting_grant.grant_id.to_le_bytes().as_ref()
+        ],
+        bump = vesting_grant.bump
+    )]
+    pub vesting_grant: Account<'info, VestingGrant>,
+}
+
+pub fn handler(
+    ctx: Context<CreateVestingGrant>,
+    grant_id: u64,
+    total_amount: u64,
+    start_time: i64,
+    cliff_months: u8,
+    vesting_months: u8,
+    revocable: bool,
+) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    
+    require!(total_amount > 0, PresaleError::InvalidVestingSchedule);
+    require!(
+        vesting_months > 0
+            && vesting_months as usize <= MAX_GRANT_MONTHS
+            && cliff_months <= vesting_months,
+        PresaleError::InvalidVestingSchedule
+    );
+    
+    // Grants share the reserved allocation with `grant_allocation`
+    let reserved_tokens_granted = presale_config
+        .reserved_tokens_granted
+        .checked_add(total_amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    require!(
+        reserved_tokens_granted <= presale_config.reserved_allocation,
+        PresaleError::ExceedsReservedAllocation
+    );
+    presale_config.reserved_tokens_granted = reserved_tokens_granted;
+    
+    let vesting_grant = &mut ctx.accounts.vesting_grant;
+    vesting_grant.beneficiary = ctx.accounts.beneficiary.key();
+    vesting_grant.grant_id = grant_id;
+    vesting_grant.total_amount = total_amount;
+    vesting_grant.start_time = start_time;
+    vesting_grant.cliff_months = cliff_months;
+    vesting_grant.vesting_months = vesting_months;
+    vesting_grant.revocable = revocable;
+    vesting_grant.is_revoked = false;
+    vesting_grant.build_schedule()?;
+    vesting_grant.bump = ctx.bumps.vesting_grant;
+    
+    msg!(
+        "Vesting grant of {} tokens to {} ({} month cliff, {} months)",
+        total_amount,
+        vesting_grant.beneficiary,
+        cliff_months,
+        vesting_months
+    );
+    emit!(GrantEvent {
+        authority: ctx.accounts.authority.key(),
+        beneficiary: vesting_grant.beneficiary,
+        kind: GrantKind::VestingGrant,
+        amount: total_amount,
+        is_revoked: false,
+    });
+    Ok(())
+}
+
+pub fn handler_revoke(ctx: Context<RevokeVestingGrant>) -> Result<()> {
+    let presale_config = &mut ctx.accounts.presale_config;
+    let vesting_grant = &mut ctx.accounts.vesting_grant;
+    let clock = Clock::get()?;
+    
+    require!(vesting_grant.revocable, PresaleError::GrantNotRevocable);
+    require!(!vesting_grant.is_revoked, PresaleError::GrantRevoked);
+    
+    // Released entries stay claimable; the rest returns to the reserved allocation
+    let unvested_amount = vesting_grant.unvested_amount(clock.unix_timestamp);
+    for entry in vesting_grant.schedule.iter_mut() {
+        if clock.unix_timestamp < entry.release_time {
+            entry.amount = 0;
+        }
+    }
+    vesting_grant.total_amount -= unvested_amount;
+    vesting_grant.is_revoked = true;
+    presale_config.reserved_tokens_granted = presale_config
+        .reserved_tokens_granted
+        .checked_sub(unvested_amount)
+        .ok_or(PresaleError::MathOverflow)?;
+    
+    msg!(
+        "Revoked {} unvested tokens from {}",
+        unvested_amount,
+        vesting_grant.beneficiary
+    );
+    emit!(GrantEvent {
+        authority: ctx.accounts.authority.key(),
+        beneficiary: vesting_grant.beneficiary,
+        kind: GrantKind::VestingGrant,
+        amount: unvested_amount,
+        is_revoked: true,
+    });
+    Ok(())
+}
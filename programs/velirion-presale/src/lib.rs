@@ -1,12 +1,24 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod lottery_draw;
+pub mod merkle;
+pub mod oracle;
+pub mod payouts;
+pub mod pricing;
 pub mod state;
+pub mod token_fees;
+#[cfg(test)]
+mod test_fixtures;
 
 use anchor_lang::prelude::*;
 
 pub use constants::*;
 pub use error::*;
+pub use events::*;
 pub use instructions::*;
 pub use state::*;
 
@@ -16,6 +28,7 @@ declare_id!("91uNkK5URavMx6onv6c8XTZ6VkEj4RzA6Xa4pQydWs2s");
 pub mod velirion_presale {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         ctx: Context<Initialize>,
         phases: [PresalePhase; 10],
@@ -26,6 +39,8 @@ pub mod velirion_presale {
         launch_timestamp: i64,
         vesting_launch_percentage: u8,
         vesting_monthly_percentage: u8,
+        allow_freeze_authority: bool,
+        allow_usdc_freeze_authority: bool,
     ) -> Result<()> {
         super::instructions::initialize::handler(
             ctx,
@@ -37,15 +52,59 @@ pub mod velirion_presale {
             launch_timestamp,
             vesting_launch_percentage,
             vesting_monthly_percentage,
+            allow_freeze_authority,
+            allow_usdc_freeze_authority,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn purchase(
         ctx: Context<Purchase>,
         token_amount: u64,
-        payment_type: PaymentType,
-    ) -> Result<()> {
-        super::instructions::purchase::handler(ctx, token_amount, payment_type)
+        payment_mint: Pubkey,
+        max_payment: Option<u64>,
+        expected_phase: Option<u8>,
+        deadline: Option<i64>,
+        referrer: Option<Pubkey>,
+        promo_code: Option<String>,
+        client_order_id: Option<[u8; 32]>,
+    ) -> Result<PurchaseResult> {
+        super::instructions::purchase::handler(
+            ctx,
+            token_amount,
+            payment_mint,
+            max_payment,
+            expected_phase,
+            deadline,
+            referrer,
+            promo_code,
+            client_order_id,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn purchase_exact_spend(
+        ctx: Context<Purchase>,
+        spend_amount: u64,
+        payment_mint: Pubkey,
+        min_tokens: Option<u64>,
+        expected_phase: Option<u8>,
+        deadline: Option<i64>,
+        referrer: Option<Pubkey>,
+        promo_code: Option<String>,
+        client_order_id: Option<[u8; 32]>,
+    ) -> Result<PurchaseResult> {
+        super::instructions::purchase::handler_exact_spend(
+            ctx,
+            spend_amount,
+            payment_mint,
+            min_tokens,
+            expected_phase,
+            deadline,
+            referrer,
+            promo_code,
+            client_order_id,
+        )
     }
 
     pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
@@ -64,21 +123,340 @@ pub mod velirion_presale {
         super::instructions::burn_unsold::handler(ctx)
     }
 
-    pub fn get_purchase_status(ctx: Context<GetPurchaseStatus>) -> Result<()> {
+    pub fn get_purchase_status(ctx: Context<GetPurchaseStatus>) -> Result<PurchaseStatus> {
         super::instructions::get_status::handler(ctx)
     }
 
+    pub fn get_sale_stats(ctx: Context<GetSaleStats>) -> Result<SaleStats> {
+        super::instructions::get_status::handler_sale_stats(ctx)
+    }
+
     pub fn update_config(
         ctx: Context<UpdateConfig>,
         max_purchase_per_transaction: Option<u64>,
         max_purchase_per_wallet: Option<u64>,
         min_time_between_purchases: Option<i64>,
+        auto_rollover: Option<bool>,
+        carry_over_unsold: Option<bool>,
+        trim_to_limits: Option<bool>,
     ) -> Result<()> {
         super::instructions::update_config::handler(
             ctx,
             max_purchase_per_transaction,
             max_purchase_per_wallet,
             min_time_between_purchases,
+            auto_rollover,
+            carry_over_unsold,
+            trim_to_limits,
+        )
+    }
+
+    pub fn set_discount_tiers(ctx: Context<UpdateConfig>, tiers: Vec<DiscountTier>) -> Result<()> {
+        super::instructions::update_config::handler_discount_tiers(ctx, tiers)
+    }
+
+    pub fn set_referral_config(
+        ctx: Context<UpdatePool>,
+        reward: ReferralReward,
+        reward_bps: u16,
+        referral_pool: u64,
+    ) -> Result<()> {
+        super::instructions::update_config::handler_referral(ctx, reward, reward_bps, referral_pool)
+    }
+
+    pub fn set_bonus_pool(
+        ctx: Context<UpdatePool>,
+        bonus_pool: u64,
+        vesting_launch_percentage: u8,
+        vesting_monthly_percentage: u8,
+    ) -> Result<()> {
+        super::instructions::update_config::handler_bonus_pool(
+            ctx,
+            bonus_pool,
+            vesting_launch_percentage,
+            vesting_monthly_percentage,
+        )
+    }
+
+    pub fn set_reserved_allocation(ctx: Context<UpdatePool>, reserved_allocation: u64) -> Result<()> {
+        super::instructions::update_config::handler_reserved_allocation(ctx, reserved_allocation)
+    }
+
+    pub fn add_payment_mint(
+        ctx: Context<AddPaymentMint>,
+        usd_pegged: bool,
+        prices: [u64; PHASE_COUNT],
+    ) -> Result<()> {
+        super::instructions::payment_mint::handler(ctx, usd_pegged, prices)
+    }
+
+    pub fn update_payment_mint(
+        ctx: Context<UpdatePaymentMint>,
+        is_enabled: Option<bool>,
+        usd_pegged: Option<bool>,
+        prices: Option<[u64; PHASE_COUNT]>,
+    ) -> Result<()> {
+        super::instructions::payment_mint::handler_update(ctx, is_enabled, usd_pegged, prices)
+    }
+
+    pub fn set_sol_price_feed(
+        ctx: Context<SetSolPriceFeed>,
+        price_feed: Option<Pubkey>,
+        max_price_age: i64,
+        max_confidence_bps: u16,
+    ) -> Result<()> {
+        super::instructions::price_feed::handler(ctx, price_feed, max_price_age, max_confidence_bps)
+    }
+
+    pub fn initialize_exchange_rate(
+        ctx: Context<InitializeExchangeRate>,
+        rate_updater: Pubkey,
+        rate: u64,
+        max_deviation_bps: u16,
+        max_age: i64,
+        min_update_interval: i64,
+    ) -> Result<()> {
+        super::instructions::exchange_rate::handler(
+            ctx,
+            rate_updater,
+            rate,
+            max_deviation_bps,
+            max_age,
+            min_update_interval,
+        )
+    }
+
+    pub fn update_exchange_rate(ctx: Context<UpdateExchangeRate>, rate: u64) -> Result<()> {
+        super::instructions::exchange_rate::handler_update(ctx, rate)
+    }
+
+    pub fn configure_exchange_rate(
+        ctx: Context<ConfigureExchangeRate>,
+        rate_updater: Option<Pubkey>,
+        max_deviation_bps: Option<u16>,
+        max_age: Option<i64>,
+        min_update_interval: Option<i64>,
+        use_exchange_rate: Option<bool>,
+    ) -> Result<()> {
+        super::instructions::exchange_rate::handler_configure(
+            ctx,
+            rate_updater,
+            max_deviation_bps,
+            max_age,
+            min_update_interval,
+            use_exchange_rate,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_dutch_auction(
+        ctx: Context<CreateDutchAuction>,
+        round_id: u64,
+        ceiling_price: u64,
+        floor_price: u64,
+        price_denominator: u64,
+        start_time: i64,
+        end_time: i64,
+        price_step: i64,
+        tokens_allocated: u64,
+        uniform_clearing: bool,
+    ) -> Result<()> {
+        super::instructions::dutch_auction::handler(
+            ctx,
+            round_id,
+            ceiling_price,
+            floor_price,
+            price_denominator,
+            start_time,
+            end_time,
+            price_step,
+            tokens_allocated,
+            uniform_clearing,
         )
     }
+
+    pub fn commit_dutch_auction(
+        ctx: Context<CommitDutchAuction>,
+        token_amount: u64,
+        max_price: Option<u64>,
+    ) -> Result<()> {
+        super::instructions::dutch_auction::handler_commit(ctx, token_amount, max_price)
+    }
+
+    pub fn settle_dutch_auction(ctx: Context<SettleDutchAuction>) -> Result<()> {
+        super::instructions::dutch_auction::handler_settle(ctx)
+    }
+
+    pub fn claim_dutch_auction(ctx: Context<ClaimDutchAuction>) -> Result<()> {
+        super::instructions::dutch_auction::handler_claim(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_fair_launch(
+        ctx: Context<CreateFairLaunch>,
+        round_id: u64,
+        price_sol: u64,
+        price_usdc: u64,
+        price_denominator: u64,
+        start_time: i64,
+        end_time: i64,
+        tokens_allocated: u64,
+    ) -> Result<()> {
+        super::instructions::fair_launch::handler(
+            ctx,
+            round_id,
+            price_sol,
+            price_usdc,
+            price_denominator,
+            start_time,
+            end_time,
+            tokens_allocated,
+        )
+    }
+
+    pub fn deposit_fair_launch(
+        ctx: Context<DepositFairLaunch>,
+        amount: u64,
+        payment_mint: Pubkey,
+    ) -> Result<()> {
+        super::instructions::fair_launch::handler_deposit(ctx, amount, payment_mint)
+    }
+
+    pub fn settle_fair_launch(ctx: Context<SettleFairLaunch>) -> Result<()> {
+        super::instructions::fair_launch::handler_settle(ctx)
+    }
+
+    pub fn claim_fair_launch(ctx: Context<ClaimFairLaunch>) -> Result<()> {
+        super::instructions::fair_launch::handler_claim(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_lottery(
+        ctx: Context<CreateLottery>,
+        round_id: u64,
+        seed_commitment: [u8; 32],
+        ticket_price: u64,
+        tokens_per_winner: u64,
+        winner_count: u32,
+        start_time: i64,
+        end_time: i64,
+        reveal_deadline: i64,
+    ) -> Result<()> {
+        super::instructions::lottery::handler(
+            ctx,
+            round_id,
+            seed_commitment,
+            ticket_price,
+            tokens_per_winner,
+            winner_count,
+            start_time,
+            end_time,
+            reveal_deadline,
+        )
+    }
+
+    pub fn register_lottery(ctx: Context<RegisterLottery>) -> Result<()> {
+        super::instructions::lottery::handler_register(ctx)
+    }
+
+    pub fn reveal_lottery(ctx: Context<RevealLottery>, seed: [u8; 32]) -> Result<()> {
+        super::instructions::lottery::handler_reveal(ctx, seed)
+    }
+
+    pub fn cancel_lottery(ctx: Context<CancelLottery>) -> Result<()> {
+        super::instructions::lottery::handler_cancel(ctx)
+    }
+
+    pub fn claim_lottery(ctx: Context<ClaimLottery>) -> Result<()> {
+        super::instructions::lottery::handler_claim(ctx)
+    }
+
+    pub fn register_referrer(ctx: Context<RegisterReferrer>, wallet: Pubkey) -> Result<()> {
+        super::instructions::referral::handler(ctx, wallet)
+    }
+
+    pub fn set_referrer_active(ctx: Context<SetReferrerActive>, is_active: bool) -> Result<()> {
+        super::instructions::referral::handler_set_active(ctx, is_active)
+    }
+
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        super::instructions::referral::handler_claim(ctx)
+    }
+
+    pub fn create_promo_code(
+        ctx: Context<CreatePromoCode>,
+        code_hash: [u8; 32],
+        discount_bps: u16,
+        max_uses: u32,
+        expires_at: i64,
+        phase: Option<u8>,
+    ) -> Result<()> {
+        super::instructions::promo_code::handler(ctx, code_hash, discount_bps, max_uses, expires_at, phase)
+    }
+
+    pub fn update_promo_code(
+        ctx: Context<UpdatePromoCode>,
+        max_uses: Option<u32>,
+        expires_at: Option<i64>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        super::instructions::promo_code::handler_update(ctx, max_uses, expires_at, is_active)
+    }
+
+    pub fn grant_allocation(
+        ctx: Context<GrantAllocation>,
+        reference: [u8; 32],
+        token_amount: u64,
+    ) -> Result<()> {
+        super::instructions::otc_grant::handler(ctx, reference, token_amount)
+    }
+
+    pub fn revoke_allocation(ctx: Context<RevokeAllocation>) -> Result<()> {
+        super::instructions::otc_grant::handler_revoke(ctx)
+    }
+
+    pub fn create_vesting_grant(
+        ctx: Context<CreateVestingGrant>,
+        grant_id: u64,
+        total_amount: u64,
+        start_time: i64,
+        cliff_months: u8,
+        vesting_months: u8,
+        revocable: bool,
+    ) -> Result<()> {
+        super::instructions::vesting_grant::handler(
+            ctx,
+            grant_id,
+            total_amount,
+            start_time,
+            cliff_months,
+            vesting_months,
+            revocable,
+        )
+    }
+
+    pub fn revoke_vesting_grant(ctx: Context<RevokeVestingGrant>) -> Result<()> {
+        super::instructions::vesting_grant::handler_revoke(ctx)
+    }
+
+    pub fn claim_vesting_grant(ctx: Context<ClaimVestingGrant>) -> Result<()> {
+        super::instructions::claim_vested::handler_grant(ctx)
+    }
+
+    pub fn create_merkle_distributor(
+        ctx: Context<CreateMerkleDistributor>,
+        round_id: u64,
+        root: [u8; 32],
+        total_amount: u64,
+    ) -> Result<()> {
+        super::instructions::merkle_distributor::handler(ctx, round_id, root, total_amount)
+    }
+
+    pub fn claim_merkle_allocation(
+        ctx: Context<ClaimMerkleAllocation>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        super::instructions::merkle_distributor::handler_claim(ctx, amount, proof)
+    }
 }
@@ -0,0 +1,137 @@
+use solana_sha256_hasher::hashv;
+
+// Winner selection for the lottery round. Everything here is a pure function
+// of the revealed seed, so a draw can be replayed off-chain from fixed seeds.
+//
+// Tickets are numbered in registration order. A keyed Feistel permutation
+// shuffles the indices; the tickets that land in the first `winner_count`
+// positions win. Each claim checks its own ticket, so no instruction ever has
+// to walk the full ticket list.
+
+const FEISTEL_ROUNDS: u8 = 4;
+
+/// Commitment the authority publishes before registration opens.
+pub fn seed_commitment(seed: &[u8; 32]) -> [u8; 32] {
+    hashv(&[seed]).to_bytes()
+}
+
+/// Folds a registrant into the running hash of everyone who entered.
+pub fn add_entrant(entrants_hash: &[u8; 32], entrant: &[u8]) -> [u8; 32] {
+    hashv(&[entrants_hash, entrant]).to_bytes()
+}
+
+/// Draw randomness: the revealed seed mixed with the entrant list, so the
+/// authority cannot know the outcome when committing.
+pub fn draw_randomness(seed: &[u8; 32], entrants_hash: &[u8; 32]) -> [u8; 32] {
+    hashv(&[seed, entrants_hash]).to_bytes()
+}
+
+pub fn is_winner(randomness: &[u8; 32], ticket_index: u32, ticket_count: u32, winner_count: u32) -> bool {
+    if ticket_count <= winner_count {
+        return true;
+    }
+    permute(randomness, ticket_index, ticket_count) < winner_count
+}
+
+/// Bijection on `0..count`. The Feistel network permutes a power-of-four
+/// domain covering `count`; cycle-walking maps values outside `count` back in.
+fn permute(randomness: &[u8; 32], index: u32, count: u32) -> u32 {
+    let bits = 32 - (count - 1).leading_zeros();
+    let half_bits = bits.div_ceil(2).max(1);
+
+    let mut value = index as u64;
+    loop {
+        value = feistel(randomness, value, half_bits);
+        if value < count as u64 {
+            return value as u32;
+        }
+    }
+}
+
+fn feistel(randomness: &[u8; 32], value: u64, half_bits: u32) -> u64 {
+    let mask = (1u64 << half_bits) - 1;
+    let mut left = value >> half_bits;
+    let mut right = value & mask;
+    for round in 0..FEISTEL_ROUNDS {
+        let digest = hashv(&[randomness.as_ref(), &[round], &right.to_le_bytes()]).to_bytes();
+        let f = u64::from_le_bytes(digest[..8].try_into().unwrap()) & mask;
+        (left, right) = (right, left ^ f);
+    }
+    (left << half_bits) | right
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fixed inputs, so a failing draw can be replayed exactly
+    const SEED: [u8; 32] = [7; 32];
+
+    fn entrants_hash(count: u8) -> [u8; 32] {
+        (0..count).fold([0; 32], |hash, i| add_entrant(&hash, &[i; 32]))
+    }
+
+    fn winners(randomness: &[u8; 32], ticket_count: u32, winner_count: u32) -> Vec<u32> {
+        (0..ticket_count)
+            .filter(|&index| is_winner(randomness, index, ticket_count, winner_count))
+            .collect()
+    }
+
+    #[test]
+    fn commitment_binds_the_seed() {
+        assert_eq!(seed_commitment(&SEED), seed_commitment(&SEED));
+        assert_ne!(seed_commitment(&SEED), seed_commitment(&[8; 32]));
+    }
+
+    #[test]
+    fn permutation_is_a_bijection() {
+        let randomness = draw_randomness(&SEED, &entrants_hash(3));
+        for count in 1..=70u32 {
+            let mut seen = vec![false; count as usize];
+            for index in 0..count {
+                let permuted = permute(&randomness, index, count);
+                assert!(permuted < count);
+                assert!(!seen[permuted as usize]);
+                seen[permuted as usize] = true;
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_seed_draw_picks_exactly_winner_count() {
+        let randomness = draw_randomness(&SEED, &entrants_hash(10));
+        let drawn = winners(&randomness, 10, 3);
+        assert_eq!(drawn.len(), 3);
+        // Replaying the same inputs gives the same winners
+        let replayed = draw_randomness(&SEED, &entrants_hash(10));
+        assert_eq!(winners(&replayed, 10, 3), drawn);
+
+        for ticket_count in [4, 17, 100, 257] {
+            assert_eq!(winners(&randomness, ticket_count, 3).len(), 3);
+        }
+    }
+
+    #[test]
+    fn every_input_changes_the_draw() {
+        let base = winners(&draw_randomness(&SEED, &entrants_hash(10)), 10, 3);
+        let other_seed = winners(&draw_randomness(&[8; 32], &entrants_hash(10)), 10, 3);
+        let other_entrants = winners(&draw_randomness(&SEED, &entrants_hash(11)), 10, 3);
+        assert_ne!(base, other_seed);
+        assert_ne!(base, other_entrants);
+    }
+
+    #[test]
+    fn undersubscribed_lottery_lets_everyone_win() {
+        let randomness = draw_randomness(&SEED, &entrants_hash(2));
+        assert_eq!(winners(&randomness, 2, 5), vec![0, 1]);
+        assert_eq!(winners(&randomness, 5, 5), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn fixed_seed_draw_matches_the_off_chain_replay() {
+        // Same vectors as lotteryIsWinner in tests/utils.ts
+        let randomness = draw_randomness(&SEED, &entrants_hash(10));
+        assert_eq!(winners(&randomness, 10, 3), vec![5, 6, 8]);
+        assert_eq!(winners(&randomness, 2, 1), vec![1]);
+    }
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use solana_sha256_hasher::hashv;
+
+// Merkle proofs for the distributor. Leaves and inner nodes are hashed with
+// different prefixes so an inner node can never be passed off as a leaf, and
+// each pair is sorted before hashing so proofs need no left/right flags.
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Leaf for a `(wallet, amount)` allocation.
+pub fn leaf(wallet: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, wallet.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Parent of two nodes, independent of their order.
+pub fn parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, first, second]).to_bytes()
+}
+
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| parent(&node, sibling));
+    computed == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Allocation = (Pubkey, u64);
+
+    // Four allocations: root = parent(parent(a, b), parent(c, d))
+    fn tree() -> (Vec<Allocation>, [[u8; 32]; 4], [u8; 32]) {
+        let allocations: Vec<Allocation> =
+            (1..=4).map(|amount| (Pubkey::new_unique(), amount * 1_000)).collect();
+        let leaves = [0, 1, 2, 3].map(|i| leaf(&allocations[i].0, allocations[i].1));
+        let root = parent(&parent(&leaves[0], &leaves[1]), &parent(&leaves[2], &leaves[3]));
+        (allocations, leaves, root)
+    }
+
+    #[test]
+    fn parent_does_not_depend_on_order() {
+        let (_, leaves, _) = tree();
+        assert_eq!(parent(&leaves[0], &leaves[1]), parent(&leaves[1], &leaves[0]));
+    }
+
+    #[test]
+    fn every_allocation_verifies_against_the_root() {
+        let (allocations, leaves, root) = tree();
+
+        let proofs = [
+            [leaves[1], parent(&leaves[2], &leaves[3])],
+            [leaves[0], parent(&leaves[2], &leaves[3])],
+            [leaves[3], parent(&leaves[0], &leaves[1])],
+            [leaves[2], parent(&leaves[0], &leaves[1])],
+        ];
+        for ((wallet, amount), proof) in allocations.iter().zip(proofs.iter()) {
+            assert!(verify(proof, &root, leaf(wallet, *amount)));
+        }
+    }
+
+    #[test]
+    fn altered_claims_do_not_verify() {
+        let (allocations, leaves, root) = tree();
+        let (wallet, amount) = allocations[0];
+        let proof = [leaves[1], parent(&leaves[2], &leaves[3])];
+
+        assert!(!verify(&proof, &root, leaf(&wallet, amount + 1)));
+        assert!(!verify(&proof, &root, leaf(&allocations[1].0, amount)));
+        assert!(!verify(&proof[..1], &root, leaf(&wallet, amount)));
+    }
+}
@@ -0,0 +1,198 @@
+use anchor_lang::prelude::*;
+use crate::error::PresaleError;
+use crate::pricing::{pow10, Price};
+
+// Minimal reader for Pyth-compatible (legacy v2) price accounts. Only the
+// fields needed for pricing are decoded, so a mock account written with the
+// same layout works on a local validator.
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+
+const MAGIC_OFFSET: usize = 0;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGG_PRICE_OFFSET: usize = 208;
+const AGG_CONF_OFFSET: usize = 216;
+const AGG_STATUS_OFFSET: usize = 224;
+const MIN_ACCOUNT_LEN: usize = 240;
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// SOL/USD rate: `price * 10^expo` USD per SOL.
+#[derive(Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    /// Rejects stale prices and prices whose confidence interval is wider
+    /// than `max_confidence_bps` of the price.
+    pub fn validate(&self, current_time: i64, max_age: i64, max_confidence_bps: u16) -> Result<()> {
+        require!(self.price > 0, PresaleError::InvalidPriceFeed);
+        let age = current_time
+            .checked_sub(self.publish_time)
+            .ok_or(PresaleError::MathOverflow)?;
+        require!(age <= max_age, PresaleError::StalePriceFeed);
+        require!(
+            (self.conf as u128) * BPS_DENOMINATOR
+                <= (self.price as u128) * max_confidence_bps as u128,
+            PresaleError::PriceConfidenceTooWide
+        );
+        Ok(())
+    }
+
+    /// Converts a price in USDC base units into lamports.
+    pub fn usd_to_lamports(&self, usd_price: Price, usdc_decimals: u8) -> Result<Price> {
+        let rate = self.price as u128;
+        let exponent = u8::try_from(self.expo.unsigned_abs()).map_err(|_| PresaleError::InvalidPriceFeed)?;
+        // lamports = usdc_units * 10^9 / (10^usdc_decimals * price * 10^expo)
+        let price = usd_price.scale(pow10(9)?, pow10(usdc_decimals)?)?;
+        if self.expo < 0 {
+            price.scale(pow10(exponent)?, rate)
+        } else {
+            price.scale(1, rate.checked_mul(pow10(exponent)?).ok_or(PresaleError::MathOverflow)?)
+        }
+    }
+}
+
+pub fn read_price_feed(account: &AccountInfo) -> Result<OraclePrice> {
+    let data = account.try_borrow_data()?;
+    require!(data.len() >= MIN_ACCOUNT_LEN, PresaleError::InvalidPriceFeed);
+    require!(
+        read_u32(&data, MAGIC_OFFSET) == PYTH_MAGIC
+            && read_u32(&data, ACCOUNT_TYPE_OFFSET) == PYTH_PRICE_ACCOUNT_TYPE,
+        PresaleError::InvalidPriceFeed
+    );
+    require!(
+        read_u32(&data, AGG_STATUS_OFFSET) == PYTH_STATUS_TRADING,
+        PresaleError::InvalidPriceFeed
+    );
+
+    Ok(OraclePrice {
+        price: read_u64(&data, AGG_PRICE_OFFSET) as i64,
+        conf: read_u64(&data, AGG_CONF_OFFSET),
+        expo: read_u32(&data, EXPONENT_OFFSET) as i32,
+        publish_time: read_u64(&data, TIMESTAMP_OFFSET) as i64,
+    })
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // $150.00 per SOL, as Pyth publishes it
+    const SOL_PRICE: i64 = 15_000_000_000;
+    const SOL_EXPO: i32 = -8;
+
+    fn price_account_data(price: i64, conf: u64, expo: i32, publish_time: i64) -> Vec<u8> {
+        let mut data = vec![0u8; MIN_ACCOUNT_LEN];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[ACCOUNT_TYPE_OFFSET..ACCOUNT_TYPE_OFFSET + 4]
+            .copy_from_slice(&PYTH_PRICE_ACCOUNT_TYPE.to_le_bytes());
+        data[EXPONENT_OFFSET..EXPONENT_OFFSET + 4].copy_from_slice(&expo.to_le_bytes());
+        data[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8].copy_from_slice(&publish_time.to_le_bytes());
+        data[AGG_PRICE_OFFSET..AGG_PRICE_OFFSET + 8].copy_from_slice(&price.to_le_bytes());
+        data[AGG_CONF_OFFSET..AGG_CONF_OFFSET + 8].copy_from_slice(&conf.to_le_bytes());
+        data[AGG_STATUS_OFFSET..AGG_STATUS_OFFSET + 4]
+            .copy_from_slice(&PYTH_STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    fn read(mut data: Vec<u8>) -> Result<OraclePrice> {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        read_price_feed(&account)
+    }
+
+    fn sol_price(conf: u64, publish_time: i64) -> OraclePrice {
+        OraclePrice {
+            price: SOL_PRICE,
+            conf,
+            expo: SOL_EXPO,
+            publish_time,
+        }
+    }
+
+    #[test]
+    fn price_feed_is_decoded_from_the_pyth_layout() {
+        let price = read(price_account_data(SOL_PRICE, 1_500_000, SOL_EXPO, 1_000)).unwrap();
+        assert_eq!(price.price, SOL_PRICE);
+        assert_eq!(price.conf, 1_500_000);
+        assert_eq!(price.expo, SOL_EXPO);
+        assert_eq!(price.publish_time, 1_000);
+    }
+
+    #[test]
+    fn malformed_price_feeds_are_rejected() {
+        let valid = price_account_data(SOL_PRICE, 0, SOL_EXPO, 0);
+
+        let mut wrong_magic = valid.clone();
+        wrong_magic[MAGIC_OFFSET] ^= 1;
+        assert!(read(wrong_magic).is_err());
+
+        let mut wrong_type = valid.clone();
+        wrong_type[ACCOUNT_TYPE_OFFSET] = 2;
+        assert!(read(wrong_type).is_err());
+
+        let mut not_trading = valid.clone();
+        not_trading[AGG_STATUS_OFFSET] = 0;
+        assert!(read(not_trading).is_err());
+
+        assert!(read(valid[..MIN_ACCOUNT_LEN - 1].to_vec()).is_err());
+    }
+
+    #[test]
+    fn stale_or_uncertain_prices_fail_validation() {
+        // 1% confidence, published 60 seconds ago
+        let price = sol_price(150_000_000, 1_000);
+        assert!(price.validate(1_060, 60, 100).is_ok());
+
+        assert_eq!(
+            price.validate(1_061, 60, 100).unwrap_err(),
+            PresaleError::StalePriceFeed.into()
+        );
+        assert_eq!(
+            price.validate(1_060, 60, 99).unwrap_err(),
+            PresaleError::PriceConfidenceTooWide.into()
+        );
+
+        let negative = OraclePrice { price: -1, ..price };
+        assert_eq!(
+            negative.validate(1_000, 60, 100).unwrap_err(),
+            PresaleError::InvalidPriceFeed.into()
+        );
+    }
+
+    #[test]
+    fn usd_prices_convert_to_lamports_at_the_oracle_rate() {
+        let price = sol_price(0, 0);
+
+        // $150 per token is one SOL per token at $150/SOL
+        let lamports = price.usd_to_lamports(Price::new(150_000_000, 1).unwrap(), 6).unwrap();
+        assert_eq!(lamports.payment_for(1).unwrap(), 1_000_000_000);
+
+        // 3 micro-USDC per token is 20 lamports
+        let lamports = price.usd_to_lamports(Price::new(3, 1).unwrap(), 6).unwrap();
+        assert_eq!(lamports.payment_for(1).unwrap(), 20);
+
+        // A positive exponent scales the rate up instead
+        let price = OraclePrice { price: 15, expo: 1, ..price };
+        let lamports = price.usd_to_lamports(Price::new(150_000_000, 1).unwrap(), 6).unwrap();
+        assert_eq!(lamports.payment_for(1).unwrap(), 1_000_000_000);
+    }
+}
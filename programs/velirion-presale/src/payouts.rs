@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer as SystemTransfer};
+use anchor_spl::token_interface::{Mint, TransferChecked};
+use crate::constants::*;
+
+// Payments out of the program's vaults, used for refunds. The SOL vault is a
+// system-owned PDA; token vaults are owned by the presale config PDA.
+
+/// Sends `amount` lamports from the SOL vault to `to`.
+pub fn pay_from_sol_vault<'info>(
+    sol_vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    sol_vault_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[SOL_VAULT_SEED, &[sol_vault_bump]];
+    let signer = &[seeds];
+
+    let cpi_accounts = SystemTransfer {
+        from: sol_vault.clone(),
+        to: to.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer);
+    transfer(cpi_ctx, amount)
+}
+
+/// Sends `amount` from a token vault owned by the presale config to `to`.
+pub fn pay_from_token_vault<'info>(
+    vault: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &AccountInfo<'info>,
+    presale_config: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    presale_config_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[PRESALE_CONFIG_SEED, &[presale_config_bump]];
+    let signer = &[seeds];
+
+    let cpi_accounts = TransferChecked {
+        from: vault.clone(),
+        mint: mint.to_account_info(),
+        to: to.clone(),
+        authority: presale_config.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer);
+    anchor_spl::token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)
+}
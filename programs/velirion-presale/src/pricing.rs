@@ -0,0 +1,342 @@
+use anchor_lang::prelude::*;
+use crate::error::PresaleError;
+
+// Prices are rational: `numerator` payment base units buy `denominator` sale
+// token base units. All intermediate math runs in u128 so large orders cannot
+// overflow, and rounding always favours the program.
+
+/// A rational price in payment base units per sale token base units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Price {
+    pub numerator: u128,
+    pub denominator: u128,
+}
+
+impl Price {
+    /// Builds a price reduced to lowest terms, which keeps later scaling
+    /// (decimals, oracle conversion) well inside u128.
+    pub fn new(numerator: u128, denominator: u128) -> Result<Price> {
+        require!(denominator > 0, PresaleError::InvalidPhaseConfig);
+        let divisor = gcd(numerator, denominator).max(1);
+        Ok(Price {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        })
+    }
+
+    /// Payment owed for `token_amount` base units, rounded up.
+    pub fn payment_for(&self, token_amount: u64) -> Result<u64> {
+        to_u64(mul_div_ceil(token_amount as u128, self.numerator, self.denominator)?)
+    }
+
+    /// Sale token base units that `payment_amount` can buy, rounded down.
+    pub fn tokens_for(&self, payment_amount: u64) -> Result<u64> {
+        require!(self.numerator > 0, PresaleError::InvalidPhaseConfig);
+        let tokens = (payment_amount as u128)
+            .checked_mul(self.denominator)
+            .ok_or(PresaleError::MathOverflow)?
+            / self.numerator;
+        // Callers clamp the result to what is available, so saturate instead of failing
+        Ok(u64::try_from(tokens).unwrap_or(u64::MAX))
+    }
+
+    /// Multiplies the price by `multiplier / divisor`.
+    pub fn scale(&self, multiplier: u128, divisor: u128) -> Result<Price> {
+        Price::new(
+            self.numerator
+                .checked_mul(multiplier)
+                .ok_or(PresaleError::MathOverflow)?,
+            self.denominator
+                .checked_mul(divisor)
+                .ok_or(PresaleError::MathOverflow)?,
+        )
+    }
+
+    /// Takes `discount_bps` basis points off the price.
+    pub fn discount(&self, discount_bps: u16) -> Result<Price> {
+        require!(discount_bps <= 10_000, PresaleError::InvalidPhaseConfig);
+        self.scale(10_000 - discount_bps as u128, 10_000)
+    }
+
+    /// Re-expresses a price quoted in a mint with `from_decimals` in the base
+    /// units of a mint with `to_decimals`, e.g. a USDC price for USDT or PYUSD.
+    pub fn rescale(&self, from_decimals: u8, to_decimals: u8) -> Result<Price> {
+        if to_decimals >= from_decimals {
+            self.scale(pow10(to_decimals - from_decimals)?, 1)
+        } else {
+            self.scale(1, pow10(from_decimals - to_decimals)?)
+        }
+    }
+}
+
+/// A price moving linearly from `start` to `end` over a span of positions
+/// (seconds into a phase, or tokens sold in it).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PriceRange {
+    pub start: Price,
+    pub end: Price,
+}
+
+impl PriceRange {
+    pub fn flat(price: Price) -> PriceRange {
+        PriceRange { start: price, end: price }
+    }
+
+    /// Applies the same conversion (decimals, oracle rate) to both ends.
+    pub fn map(&self, convert: impl Fn(Price) -> Result<Price>) -> Result<PriceRange> {
+        Ok(PriceRange {
+            start: convert(self.start)?,
+            end: convert(self.end)?,
+        })
+    }
+
+    /// Price at `position` of `span`, clamped to the range.
+    pub fn at(&self, position: u128, span: u128) -> Result<Price> {
+        let position = position.min(span);
+        let (start, end, denominator) = self.common_terms()?;
+        Price::new(
+            weighted_sum(start, end, span - position, position)?,
+            denominator
+                .checked_mul(span)
+                .ok_or(PresaleError::MathOverflow)?,
+        )
+    }
+
+    /// Payment for `amount` base units bought from `position` onward, where the
+    /// price runs from `start` at position 0 to `end` at `span`. This is the
+    /// area under the curve, i.e. `amount` times the price at the midpoint of
+    /// the order, rounded up.
+    pub fn payment_over(&self, position: u64, amount: u64, span: u64) -> Result<u64> {
+        let double_span = 2 * span as u128;
+        let double_midpoint = 2 * position as u128 + amount as u128;
+        require!(double_midpoint <= double_span, PresaleError::InsufficientTokensInPhase);
+
+        let (start, end, denominator) = self.common_terms()?;
+        let numerator = weighted_sum(start, end, double_span - double_midpoint, double_midpoint)?;
+        let divisor = denominator
+            .checked_mul(double_span)
+            .ok_or(PresaleError::MathOverflow)?;
+        to_u64(mul_div_ceil(amount as u128, numerator, divisor)?)
+    }
+
+    /// Largest amount, at most `max_amount`, whose `payment_over` fits in
+    /// `payment_amount`.
+    pub fn tokens_over(&self, position: u64, payment_amount: u64, max_amount: u64, span: u64) -> Result<u64> {
+        let (mut low, mut high) = (0u64, max_amount);
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            // Amounts too large to price cannot be afforded either
+            match self.payment_over(position, mid, span) {
+                Ok(cost) if cost <= payment_amount => low = mid,
+                _ => high = mid - 1,
+            }
+        }
+        Ok(low)
+    }
+
+    /// Both ends over one shared denominator.
+    fn common_terms(&self) -> Result<(u128, u128, u128)> {
+        let (start, end) = (self.start, self.end);
+        if start.denominator == end.denominator {
+            return Ok((start.numerator, end.numerator, start.denominator));
+        }
+        Ok((
+            start
+                .numerator
+                .checked_mul(end.denominator)
+                .ok_or(PresaleError::MathOverflow)?,
+            end.numerator
+                .checked_mul(start.denominator)
+                .ok_or(PresaleError::MathOverflow)?,
+            start
+                .denominator
+                .checked_mul(end.denominator)
+                .ok_or(PresaleError::MathOverflow)?,
+        ))
+    }
+}
+
+/// One whole token expressed in base units, used as the default denominator.
+pub fn token_unit(decimals: u8) -> Result<u64> {
+    10u64
+        .checked_pow(decimals as u32)
+        .ok_or(PresaleError::MathOverflow.into())
+}
+
+pub fn pow10(exponent: u8) -> Result<u128> {
+    10u128
+        .checked_pow(exponent as u32)
+        .ok_or(PresaleError::MathOverflow.into())
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// `value * multiplier / divisor` rounded up. Splitting the multiplier into
+/// whole and fractional parts keeps the intermediate product small.
+fn mul_div_ceil(value: u128, multiplier: u128, divisor: u128) -> Result<u128> {
+    let whole = value
+        .checked_mul(multiplier / divisor)
+        .ok_or(PresaleError::MathOverflow)?;
+    let fraction = value
+        .checked_mul(multiplier % divisor)
+        .ok_or(PresaleError::MathOverflow)?
+        .div_ceil(divisor);
+    whole
+        .checked_add(fraction)
+        .ok_or(PresaleError::MathOverflow.into())
+}
+
+fn weighted_sum(first: u128, second: u128, first_weight: u128, second_weight: u128) -> Result<u128> {
+    first
+        .checked_mul(first_weight)
+        .and_then(|first| first.checked_add(second.checked_mul(second_weight)?))
+        .ok_or(PresaleError::MathOverflow.into())
+}
+
+fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| PresaleError::MathOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_reduces_to_lowest_terms() {
+        assert_eq!(Price::new(10, 4).unwrap(), Price { numerator: 5, denominator: 2 });
+        assert_eq!(Price::new(0, 7).unwrap(), Price { numerator: 0, denominator: 1 });
+        assert_eq!(
+            Price::new(3 * (1 << 100), 1 << 100).unwrap(),
+            Price { numerator: 3, denominator: 1 }
+        );
+        assert!(Price::new(1, 0).is_err());
+    }
+
+    #[test]
+    fn discount_lowers_the_price_before_rounding() {
+        let price = Price::new(3, 1).unwrap();
+        assert_eq!(price.discount(0).unwrap(), price);
+        assert_eq!(price.discount(1_000).unwrap(), Price::new(27, 10).unwrap());
+        // 10 tokens at 2.7 cost 27, not 30 less a floored 10%
+        assert_eq!(price.discount(1_000).unwrap().payment_for(10).unwrap(), 27);
+        assert_eq!(price.discount(1_000).unwrap().tokens_for(27).unwrap(), 10);
+        assert!(price.discount(10_001).is_err());
+    }
+
+    #[test]
+    fn payment_rounds_up_and_tokens_round_down() {
+        let price = Price::new(1, 3).unwrap();
+        assert_eq!(price.payment_for(0).unwrap(), 0);
+        assert_eq!(price.payment_for(1).unwrap(), 1);
+        assert_eq!(price.payment_for(3).unwrap(), 1);
+        assert_eq!(price.payment_for(4).unwrap(), 2);
+        assert_eq!(price.tokens_for(1).unwrap(), 3);
+        assert_eq!(price.tokens_for(2).unwrap(), 6);
+
+        // 0.1 SOL per whole token: one base unit still costs a lamport
+        let price = Price::new(100_000_000, pow10(9).unwrap()).unwrap();
+        assert_eq!(price.payment_for(1).unwrap(), 1);
+        assert_eq!(price.payment_for(1_000_000_000).unwrap(), 100_000_000);
+        assert_eq!(price.tokens_for(1).unwrap(), 10);
+        assert!(Price::new(0, 1).unwrap().tokens_for(1).is_err());
+    }
+
+    #[test]
+    fn payment_overflowing_u64_is_rejected() {
+        let price = Price::new(u64::MAX as u128, 1).unwrap();
+        assert_eq!(price.payment_for(1).unwrap(), u64::MAX);
+        assert!(price.payment_for(2).is_err());
+    }
+
+    #[test]
+    fn rescale_moves_between_decimals() {
+        let price = Price::new(10, 1).unwrap();
+        assert_eq!(price.rescale(6, 8).unwrap(), Price::new(1_000, 1).unwrap());
+        assert_eq!(price.rescale(6, 4).unwrap(), Price::new(1, 10).unwrap());
+    }
+
+    #[test]
+    fn mul_div_ceil_near_u128_limits() {
+        assert_eq!(mul_div_ceil(u128::MAX, 1, 1).unwrap(), u128::MAX);
+        assert_eq!(mul_div_ceil(u128::MAX, 7, 7).unwrap(), u128::MAX);
+        // ceil(3 * (2^127 - 1) / 2) without forming the 3 * (2^127 - 1) product
+        assert_eq!(mul_div_ceil(u128::MAX / 2, 3, 2).unwrap(), 3 * (1 << 126) - 1);
+        assert_eq!(
+            mul_div_ceil(1 << 100, 5, 3).unwrap(),
+            (5 * (1u128 << 100)).div_ceil(3)
+        );
+        assert!(mul_div_ceil(u128::MAX, 2, 1).is_err());
+        assert!(mul_div_ceil(u128::MAX, 3, 2).is_err());
+    }
+
+    #[test]
+    fn flat_range_matches_the_plain_price() {
+        let range = PriceRange::flat(Price::new(7, 3).unwrap());
+        for amount in [1, 2, 3, 1_000, 999_999] {
+            assert_eq!(
+                range.payment_over(0, amount, 1_000_000).unwrap(),
+                range.start.payment_for(amount).unwrap()
+            );
+            assert_eq!(
+                range.payment_over(500_000, amount / 2, 1_000_000).unwrap(),
+                range.start.payment_for(amount / 2).unwrap()
+            );
+        }
+        assert_eq!(range.tokens_over(0, 7, 1_000_000, 1_000_000).unwrap(), 3);
+        assert_eq!(range.tokens_over(0, 6, 1_000_000, 1_000_000).unwrap(), 2);
+        assert_eq!(range.tokens_over(0, 7_000, 10, 1_000_000).unwrap(), 10);
+    }
+
+    #[test]
+    fn sloped_range_charges_the_area_under_the_curve() {
+        // 1 to 3 payment units per token across 100 tokens
+        let range = PriceRange {
+            start: Price::new(1, 1).unwrap(),
+            end: Price::new(3, 1).unwrap(),
+        };
+        assert_eq!(range.at(0, 100).unwrap(), Price::new(1, 1).unwrap());
+        assert_eq!(range.at(50, 100).unwrap(), Price::new(2, 1).unwrap());
+        assert_eq!(range.at(150, 100).unwrap(), Price::new(3, 1).unwrap());
+
+        assert_eq!(range.payment_over(0, 100, 100).unwrap(), 200);
+        assert_eq!(range.payment_over(0, 50, 100).unwrap(), 75);
+        assert_eq!(range.payment_over(50, 50, 100).unwrap(), 125);
+        assert!(range.payment_over(50, 151, 100).is_err());
+    }
+
+    #[test]
+    fn spanning_order_costs_the_sum_of_its_parts() {
+        let range = PriceRange {
+            start: Price::new(100, 1).unwrap(),
+            end: Price::new(300, 1).unwrap(),
+        };
+        let whole = range.payment_over(0, 1_000, 1_000).unwrap();
+        let mut parts = 0;
+        for position in (0..1_000).step_by(250) {
+            parts += range.payment_over(position, 250, 1_000).unwrap();
+        }
+        assert_eq!(whole, 200_000);
+        assert_eq!(parts, whole);
+    }
+
+    #[test]
+    fn sloped_tokens_over_is_the_largest_affordable_amount() {
+        let range = PriceRange {
+            start: Price::new(1, 1).unwrap(),
+            end: Price::new(3, 1).unwrap(),
+        };
+        assert_eq!(range.tokens_over(0, 75, 100, 100).unwrap(), 50);
+        assert_eq!(range.tokens_over(50, 125, 100, 100).unwrap(), 50);
+        assert_eq!(range.tokens_over(0, 1_000, 100, 100).unwrap(), 100);
+        for payment in [0, 1, 10, 74, 199] {
+            let tokens = range.tokens_over(0, payment, 100, 100).unwrap();
+            assert!(range.payment_over(0, tokens, 100).unwrap() <= payment);
+            assert!(range.payment_over(0, tokens + 1, 100).unwrap() > payment);
+        }
+    }
+}
@@ -1,7 +1,15 @@
 use anchor_lang::prelude::*;
+use crate::error::PresaleError;
+use crate::oracle::OraclePrice;
+use crate::pricing::{token_unit, Price, PriceRange};
+use solana_sha256_hasher::hashv;
 
 pub const PHASE_COUNT: usize = 10;
+pub const MAX_DISCOUNT_TIERS: usize = 4;
+pub const EXCHANGE_RATE_DECIMALS: u8 = 8; // USD per SOL is stored as `rate / 10^8`
 pub const VESTING_MONTHS: u8 = 2; // 40% at launch + 2 months of 30% each
+pub const MONTH_SECONDS: i64 = 2_592_000; // 30 days
+pub const MAX_GRANT_MONTHS: usize = 48;
 
 #[account]
 pub struct PresaleConfig {
@@ -11,6 +19,8 @@ pub struct PresaleConfig {
     pub usdc_mint: Pubkey,
     pub sol_vault: Pubkey,
     pub usdc_vault: Pubkey,
+    pub token_decimals: u8,
+    pub usdc_decimals: u8,
     pub is_paused: bool,
     pub total_tokens_for_sale: u64,
     pub tokens_sold: u64,
@@ -21,30 +31,104 @@ pub struct PresaleConfig {
     pub min_time_between_purchases: i64, // in seconds
     pub vesting_launch_percentage: u8, // 40%
     pub vesting_monthly_percentage: u8, // 30%
+    pub auto_rollover: bool, // sold-out phase closes early and the next one starts immediately
+    pub carry_over_unsold: bool, // unsold allocation of an expired phase moves to the next phase
+    pub trim_to_limits: bool, // spend-exact purchases are trimmed to the per-tx / per-wallet caps
+    pub sol_price_feed: Pubkey, // Pyth-compatible SOL/USD feed; default = fixed `price_sol`
+    pub max_price_age: i64, // in seconds
+    pub max_confidence_bps: u16, // max confidence interval relative to price
+    pub use_exchange_rate: bool, // price SOL from the `ExchangeRate` account when no feed is usable
+    pub round_tokens_reserved: u64, // allocations of auction / fair-launch / lottery rounds not yet settled
+    pub discount_tiers: [DiscountTier; MAX_DISCOUNT_TIERS], // ascending `min_tokens`; unused tiers are zeroed
+    pub referral_reward: ReferralReward,
+    pub referral_reward_bps: u16, // of the tokens bought, or of the payment for commissions
+    pub referral_pool: u64, // referral bonus tokens, held in the treasury on top of `total_tokens_for_sale`
+    pub referral_tokens_booked: u64, // bonus tokens granted from `referral_pool`
+    pub bonus_pool: u64, // phase bonus tokens, held in the treasury on top of `total_tokens_for_sale`
+    pub bonus_tokens_granted: u64,
+    pub bonus_vesting_launch_percentage: u8,
+    pub bonus_vesting_monthly_percentage: u8,
+    pub reserved_allocation: u64, // for `grant_allocation`, held in the treasury on top of `total_tokens_for_sale`
+    pub reserved_tokens_granted: u64,
+    pub stats: SaleStats,
     pub bump: u8,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+/// Volume discount for purchases of at least `min_tokens` in one order.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct DiscountTier {
+    pub min_tokens: u64,
+    pub discount_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
 pub struct PresalePhase {
-    pub price_sol: u64,        // Price in lamports (1 SOL = 1_000_000_000 lamports)
-    pub price_usdc: u64,       // Price in USDC (1 USDC = 1_000_000 micro-USDC)
+    pub price_sol: u64,        // Lamports per `price_denominator` sale token base units
+    pub price_usdc: u64,       // Micro-USDC per `price_denominator` sale token base units
+    pub price_denominator: u64, // 0 at initialize means one whole token (10^token_decimals)
     pub start_time: i64,
     pub end_time: i64,
     pub tokens_allocated: u64,
     pub tokens_sold: u64,
+    pub curve: PriceCurve,
+    pub end_price_sol: u64,    // Price at the end of the curve; unused for `Flat`
+    pub end_price_usdc: u64,
+    pub bonus_bps: u16,        // Extra tokens on top of each fill, paid from the bonus pool
 }
 
-impl Default for PresalePhase {
-    fn default() -> Self {
-        PresalePhase {
-            price_sol: 0,
-            price_usdc: 0,
-            start_time: 0,
-            end_time: 0,
-            tokens_allocated: 0,
-            tokens_sold: 0,
+impl PresalePhase {
+    /// Spot price given the phase's start/end prices in the payment currency.
+    pub fn spot_price(&self, prices: &PriceRange, current_time: i64) -> Result<Price> {
+        match self.curve {
+            PriceCurve::Flat => Ok(prices.start),
+            PriceCurve::Time => {
+                let (elapsed, duration) = self.time_position(current_time);
+                prices.at(elapsed, duration)
+            }
+            PriceCurve::Supply => prices.at(
+                self.tokens_sold as u128,
+                self.tokens_allocated as u128,
+            ),
+        }
+    }
+
+    /// Payment for the next `token_amount` tokens of this phase, rounded up.
+    pub fn payment_for(&self, prices: &PriceRange, token_amount: u64, current_time: i64) -> Result<u64> {
+        match self.curve {
+            PriceCurve::Supply => {
+                prices.payment_over(self.tokens_sold, token_amount, self.tokens_allocated)
+            }
+            _ => self.spot_price(prices, current_time)?.payment_for(token_amount),
+        }
+    }
+
+    /// Tokens `payment_amount` buys from this phase, at most `max_tokens`.
+    pub fn tokens_for(
+        &self,
+        prices: &PriceRange,
+        payment_amount: u64,
+        max_tokens: u64,
+        current_time: i64,
+    ) -> Result<u64> {
+        match self.curve {
+            PriceCurve::Supply => prices.tokens_over(
+                self.tokens_sold,
+                payment_amount,
+                max_tokens,
+                self.tokens_allocated,
+            ),
+            _ => Ok(self
+                .spot_price(prices, current_time)?
+                .tokens_for(payment_amount)?
+                .min(max_tokens)),
         }
     }
+
+    fn time_position(&self, current_time: i64) -> (u128, u128) {
+        let duration = self.end_time.saturating_sub(self.start_time).max(1);
+        let elapsed = current_time.saturating_sub(self.start_time).clamp(0, duration);
+        (elapsed as u128, duration as u128)
+    }
 }
 
 #[account]
@@ -55,22 +139,33 @@ pub struct UserPurchase {
     pub total_spent_usdc: u64,
     pub last_purchase_time: i64,
     pub vesting_schedule: [VestingEntry; VESTING_MONTHS as usize + 1], // +1 for launch
+    pub receipt_count: u64, // index of the next purchase receipt
     pub bump: u8,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
 pub struct VestingEntry {
     pub amount: u64,
     pub release_time: i64,
     pub claimed: bool,
 }
 
-impl Default for VestingEntry {
-    fn default() -> Self {
-        VestingEntry {
-            amount: 0,
-            release_time: 0,
-            claimed: false,
+/// Total of the unclaimed entries released by `current_time`.
+pub fn claimable_amount(schedule: &[VestingEntry], current_time: i64) -> u64 {
+    let mut claimable = 0u64;
+    for entry in schedule {
+        if !entry.claimed && current_time >= entry.release_time && entry.amount > 0 {
+            claimable += entry.amount;
+        }
+    }
+    claimable
+}
+
+/// Marks the entries counted by `claimable_amount` as claimed.
+pub fn mark_claimed(schedule: &mut [VestingEntry], current_time: i64) {
+    for entry in schedule {
+        if !entry.claimed && current_time >= entry.release_time && entry.amount > 0 {
+            entry.claimed = true;
         }
     }
 }
@@ -83,6 +178,8 @@ impl PresaleConfig {
         32 + // usdc_mint
         32 + // sol_vault
         32 + // usdc_vault
+        1 +  // token_decimals
+        1 +  // usdc_decimals
         1 +  // is_paused
         8 +  // total_tokens_for_sale
         8 +  // tokens_sold
@@ -93,8 +190,84 @@ impl PresaleConfig {
         8 +  // min_time_between_purchases
         1 +  // vesting_launch_percentage
         1 +  // vesting_monthly_percentage
+        1 +  // auto_rollover
+        1 +  // carry_over_unsold
+        1 +  // trim_to_limits
+        32 + // sol_price_feed
+        8 +  // max_price_age
+        2 +  // max_confidence_bps
+        1 +  // use_exchange_rate
+        8 +  // round_tokens_reserved
+        (MAX_DISCOUNT_TIERS * (8 + 2)) + // discount_tiers
+        1 +  // referral_reward
+        2 +  // referral_reward_bps
+        8 +  // referral_pool
+        8 +  // referral_tokens_booked
+        8 +  // bonus_pool
+        8 +  // bonus_tokens_granted
+        1 +  // bonus_vesting_launch_percentage
+        1 +  // bonus_vesting_monthly_percentage
+        8 +  // reserved_allocation
+        8 +  // reserved_tokens_granted
+        SaleStats::SIZE + // stats
         1;   // bump
 
+    /// Sale supply that is neither sold nor reserved for a round that has not
+    /// settled yet.
+    pub fn unsold_supply(&self) -> u64 {
+        self.total_tokens_for_sale
+            .saturating_sub(self.tokens_sold)
+            .saturating_sub(self.round_tokens_reserved)
+    }
+
+    /// Tokens the treasury still has to pay out: the sale supply, the
+    /// referral and bonus pools and the reserved allocation, less what has
+    /// already been claimed.
+    pub fn treasury_obligations(&self) -> Result<u64> {
+        self.total_tokens_for_sale
+            .checked_add(self.referral_pool)
+            .and_then(|owed| owed.checked_add(self.bonus_pool))
+            .and_then(|owed| owed.checked_add(self.reserved_allocation))
+            .and_then(|owed| owed.checked_sub(self.stats.total_claimed))
+            .ok_or(PresaleError::MathOverflow.into())
+    }
+
+    pub fn require_treasury_covers(&self, treasury_balance: u64) -> Result<()> {
+        require!(
+            treasury_balance >= self.treasury_obligations()?,
+            PresaleError::TreasuryUnderfunded
+        );
+        Ok(())
+    }
+
+    /// Sets aside a round's allocation so phase purchases cannot sell it.
+    pub fn reserve_round_tokens(&mut self, amount: u64) -> Result<()> {
+        require!(
+            amount > 0 && amount <= self.unsold_supply(),
+            PresaleError::InvalidPhaseConfig
+        );
+        self.round_tokens_reserved = self
+            .round_tokens_reserved
+            .checked_add(amount)
+            .ok_or(PresaleError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Releases `released` tokens of round reservations, `sold` of which
+    /// count as sold; the rest return to the unsold supply.
+    pub fn release_round_tokens(&mut self, released: u64, sold: u64) -> Result<()> {
+        require!(sold <= released, PresaleError::MathOverflow);
+        self.round_tokens_reserved = self
+            .round_tokens_reserved
+            .checked_sub(released)
+            .ok_or(PresaleError::MathOverflow)?;
+        self.tokens_sold = self
+            .tokens_sold
+            .checked_add(sold)
+            .ok_or(PresaleError::MathOverflow)?;
+        Ok(())
+    }
+
     pub fn get_current_phase(&self, current_time: i64) -> Option<usize> {
         for (index, phase) in self.phases.iter().enumerate() {
             if current_time >= phase.start_time && current_time <= phase.end_time {
@@ -104,6 +277,184 @@ impl PresaleConfig {
         None
     }
 
+    /// Applies the optional rollover and carry-over rules to the phase table.
+    /// Called before and after every purchase so the stored windows and
+    /// allocations always reflect the sale's actual progress.
+    pub fn sync_phases(&mut self, current_time: i64) -> Result<()> {
+        if self.carry_over_unsold {
+            for i in 0..PHASE_COUNT - 1 {
+                if current_time <= self.phases[i].end_time {
+                    break;
+                }
+                let unsold = self.phases[i]
+                    .tokens_allocated
+                    .saturating_sub(self.phases[i].tokens_sold);
+                if unsold > 0 {
+                    self.phases[i].tokens_allocated = self.phases[i].tokens_sold;
+                    self.phases[i + 1].tokens_allocated = self.phases[i + 1]
+                        .tokens_allocated
+                        .checked_add(unsold)
+                        .ok_or(PresaleError::MathOverflow)?;
+                }
+            }
+        }
+
+        if self.auto_rollover {
+            while let Some(index) = self.get_current_phase(current_time) {
+                let phase = self.phases[index];
+                if index + 1 >= PHASE_COUNT || phase.tokens_sold < phase.tokens_allocated {
+                    break;
+                }
+
+                // Close the sold-out phase and pull every later window forward so
+                // the next phase opens right now.
+                let shift = self.phases[index + 1]
+                    .start_time
+                    .checked_sub(current_time)
+                    .ok_or(PresaleError::MathOverflow)?;
+                self.phases[index].end_time = current_time
+                    .checked_sub(1)
+                    .ok_or(PresaleError::MathOverflow)?;
+                for later in self.phases[index + 1..].iter_mut() {
+                    later.start_time = later
+                        .start_time
+                        .checked_sub(shift)
+                        .ok_or(PresaleError::MathOverflow)?;
+                    later.end_time = later
+                        .end_time
+                        .checked_sub(shift)
+                        .ok_or(PresaleError::MathOverflow)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Splits `token_amount` into per-phase fills starting at `phase_index`.
+    /// Without rollover the whole order must fit in the current phase; with
+    /// rollover any remainder spills into the following phases at their prices.
+    pub fn plan_purchase(
+        &self,
+        phase_index: usize,
+        token_amount: u64,
+        current_time: i64,
+        price_of: impl Fn(usize) -> Result<PriceRange>,
+    ) -> Result<Vec<PhaseFill>> {
+        let last_phase = if self.auto_rollover { PHASE_COUNT - 1 } else { phase_index };
+        let mut fills = Vec::new();
+        let mut remaining = token_amount;
+
+        for index in phase_index..=last_phase {
+            if remaining == 0 {
+                break;
+            }
+            let phase = &self.phases[index];
+            let available = phase
+                .tokens_allocated
+                .checked_sub(phase.tokens_sold)
+                .ok_or(PresaleError::MathOverflow)?;
+            let amount = remaining.min(available);
+            if amount == 0 {
+                continue;
+            }
+
+            let prices = price_of(index)?;
+            let payment_amount = phase.payment_for(&prices, amount, current_time)?;
+
+            fills.push(PhaseFill {
+                phase: index as u8,
+                token_amount: amount,
+                price: phase
+                    .spot_price(&prices, current_time)?
+                    .payment_for(token_unit(self.token_decimals)?)?,
+                payment_amount,
+            });
+            remaining -= amount;
+        }
+
+        require!(remaining == 0, PresaleError::InsufficientTokensInPhase);
+        Ok(fills)
+    }
+
+    /// Most tokens a spend-exact order may buy: never more than the unsold
+    /// supply, and with `trim_to_limits` also cut to the per-transaction cap and
+    /// what is left of the wallet's allocation. Without trimming, orders over
+    /// the limits are left for the limit checks to reject.
+    pub fn spend_token_cap(&self, user_purchase: &UserPurchase) -> u64 {
+        let cap = if self.trim_to_limits {
+            user_purchase
+                .get_remaining_allocation(self.max_purchase_per_wallet)
+                .min(self.max_purchase_per_transaction)
+        } else {
+            u64::MAX
+        };
+        cap.min(self.unsold_supply())
+    }
+
+    /// Spend-exact counterpart of `plan_purchase`: buys as many tokens as
+    /// `spend_amount` affords, never more than `max_tokens`, moving on to the
+    /// next phase (rollover only) once the current one is exhausted.
+    pub fn plan_spend(
+        &self,
+        phase_index: usize,
+        spend_amount: u64,
+        max_tokens: u64,
+        current_time: i64,
+        price_of: impl Fn(usize) -> Result<PriceRange>,
+    ) -> Result<Vec<PhaseFill>> {
+        let last_phase = if self.auto_rollover { PHASE_COUNT - 1 } else { phase_index };
+        let mut fills = Vec::new();
+        let mut spend_left = spend_amount;
+        let mut tokens_left = max_tokens;
+
+        for index in phase_index..=last_phase {
+            if spend_left == 0 || tokens_left == 0 {
+                break;
+            }
+            let phase = &self.phases[index];
+            let available = phase
+                .tokens_allocated
+                .checked_sub(phase.tokens_sold)
+                .ok_or(PresaleError::MathOverflow)?;
+            if available == 0 {
+                continue;
+            }
+
+            let prices = price_of(index)?;
+            let amount = phase.tokens_for(
+                &prices,
+                spend_left,
+                available.min(tokens_left),
+                current_time,
+            )?;
+            if amount == 0 {
+                break;
+            }
+            let payment_amount = phase.payment_for(&prices, amount, current_time)?;
+
+            fills.push(PhaseFill {
+                phase: index as u8,
+                token_amount: amount,
+                price: phase
+                    .spot_price(&prices, current_time)?
+                    .payment_for(token_unit(self.token_decimals)?)?,
+                payment_amount,
+            });
+            spend_left = spend_left
+                .checked_sub(payment_amount)
+                .ok_or(PresaleError::MathOverflow)?;
+            tokens_left -= amount;
+
+            // Only spill into the next phase when this one is sold out
+            if amount < available {
+                break;
+            }
+        }
+
+        Ok(fills)
+    }
+
     pub fn get_phase_price(&self, phase_index: usize, payment_type: PaymentType) -> Option<u64> {
         if phase_index >= PHASE_COUNT {
             return None;
@@ -111,6 +462,94 @@ impl PresaleConfig {
         match payment_type {
             PaymentType::Sol => Some(self.phases[phase_index].price_sol),
             PaymentType::Usdc => Some(self.phases[phase_index].price_usdc),
+            PaymentType::Spl => None,
+        }
+    }
+
+    /// Rational price of a phase for the built-in SOL / USDC payment paths.
+    pub fn phase_price(&self, phase_index: usize, payment_type: PaymentType) -> Result<Price> {
+        let numerator = self
+            .get_phase_price(phase_index, payment_type)
+            .ok_or(PresaleError::InvalidPaymentType)?;
+        Price::new(
+            numerator as u128,
+            self.phases[phase_index].price_denominator as u128,
+        )
+    }
+
+    /// Start and end prices of a phase's curve for the built-in SOL / USDC
+    /// payment paths. Flat phases start and end at the same price.
+    pub fn phase_price_range(&self, phase_index: usize, payment_type: PaymentType) -> Result<PriceRange> {
+        let start = self.phase_price(phase_index, payment_type)?;
+        let phase = &self.phases[phase_index];
+        if phase.curve == PriceCurve::Flat {
+            return Ok(PriceRange::flat(start));
+        }
+        let end_price = match payment_type {
+            PaymentType::Sol => phase.end_price_sol,
+            PaymentType::Usdc => phase.end_price_usdc,
+            PaymentType::Spl => return err!(PresaleError::InvalidPaymentType),
+        };
+        Ok(PriceRange {
+            start,
+            end: Price::new(end_price as u128, phase.price_denominator as u128)?,
+        })
+    }
+
+    /// Price curve of a phase in whatever the buyer pays with. SOL is derived
+    /// from the USD prices when a SOL/USD rate is available; registered SPL
+    /// mints use their registry entry.
+    pub fn payment_price(
+        &self,
+        phase_index: usize,
+        payment_type: PaymentType,
+        payment_mint_config: Option<&PaymentMintConfig>,
+        sol_usd: Option<&OraclePrice>,
+    ) -> Result<PriceRange> {
+        match (payment_type, payment_mint_config, sol_usd) {
+            (PaymentType::Spl, Some(entry), _) => entry.phase_price_range(self, phase_index),
+            (PaymentType::Sol, _, Some(sol_usd)) => self
+                .phase_price_range(phase_index, PaymentType::Usdc)?
+                .map(|price| sol_usd.usd_to_lamports(price, self.usdc_decimals)),
+            _ => self.phase_price_range(phase_index, payment_type),
+        }
+    }
+
+    /// Discount of the largest tier `token_amount` reaches, in basis points.
+    pub fn discount_bps_for(&self, token_amount: u64) -> u16 {
+        self.discount_tiers
+            .iter()
+            .filter(|tier| tier.discount_bps > 0 && token_amount >= tier.min_tokens)
+            .map(|tier| tier.discount_bps)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Phase bonus earned by `fills`, rounded down and capped at what is left
+    /// in the bonus pool.
+    pub fn bonus_for(&self, fills: &[PhaseFill]) -> Result<u64> {
+        let mut bonus: u64 = 0;
+        for fill in fills {
+            let bonus_bps = self.phases[fill.phase as usize].bonus_bps as u128;
+            let fill_bonus = (fill.token_amount as u128 * bonus_bps / 10_000) as u64;
+            bonus = bonus.checked_add(fill_bonus).ok_or(PresaleError::MathOverflow)?;
+        }
+        Ok(bonus.min(self.bonus_pool.saturating_sub(self.bonus_tokens_granted)))
+    }
+
+    pub fn uses_sol_price_feed(&self) -> bool {
+        self.sol_price_feed != Pubkey::default()
+    }
+
+    /// Classifies a payment mint argument. The native mint stands for SOL paid
+    /// from the buyer's wallet; anything other than USDC needs a registry entry.
+    pub fn payment_type_for(&self, payment_mint: &Pubkey) -> PaymentType {
+        if *payment_mint == anchor_spl::token::spl_token::native_mint::ID {
+            PaymentType::Sol
+        } else if *payment_mint == self.usdc_mint {
+            PaymentType::Usdc
+        } else {
+            PaymentType::Spl
         }
     }
 }
@@ -123,16 +562,11 @@ impl UserPurchase {
         8 +  // total_spent_usdc
         8 +  // last_purchase_time
         ((VESTING_MONTHS as usize + 1) * std::mem::size_of::<VestingEntry>()) + // vesting_schedule
+        8 +  // receipt_count
         1;   // bump
 
     pub fn get_claimable_amount(&self, current_time: i64) -> u64 {
-        let mut claimable = 0u64;
-        for entry in &self.vesting_schedule {
-            if !entry.claimed && current_time >= entry.release_time && entry.amount > 0 {
-                claimable += entry.amount;
-            }
-        }
-        claimable
+        claimable_amount(&self.vesting_schedule, current_time)
     }
 
     pub fn get_remaining_allocation(&self, max_per_wallet: u64) -> u64 {
@@ -141,11 +575,1293 @@ impl UserPurchase {
         }
         max_per_wallet - self.total_purchased
     }
+
+    /// Adds `token_amount` to the vesting schedule: `launch_percentage` at
+    /// launch and `monthly_percentage` every 30 days after it.
+    pub fn add_vesting(
+        &mut self,
+        token_amount: u64,
+        launch_timestamp: i64,
+        launch_percentage: u8,
+        monthly_percentage: u8,
+    ) -> Result<()> {
+        let launch_amount = token_amount
+            .checked_mul(launch_percentage as u64)
+            .and_then(|a| a.checked_div(100))
+            .ok_or(PresaleError::MathOverflow)?;
+
+        let monthly_amount = token_amount
+            .checked_mul(monthly_percentage as u64)
+            .and_then(|a| a.checked_div(100))
+            .ok_or(PresaleError::MathOverflow)?;
+
+        // Launch vesting (40% at launch)
+        if self.vesting_schedule[0].release_time == 0 {
+            self.vesting_schedule[0] = VestingEntry {
+                amount: launch_amount,
+                release_time: launch_timestamp,
+                claimed: false,
+            };
+        } else {
+            self.vesting_schedule[0].amount = self.vesting_schedule[0]
+                .amount
+                .checked_add(launch_amount)
+                .ok_or(PresaleError::MathOverflow)?;
+        }
+
+        // Monthly vesting (30% each month)
+        for i in 1..=VESTING_MONTHS as usize {
+            let release_time = launch_timestamp
+                .checked_add((i as i64).checked_mul(MONTH_SECONDS).ok_or(PresaleError::MathOverflow)?)
+                .ok_or(PresaleError::MathOverflow)?;
+
+            if self.vesting_schedule[i].release_time == 0 {
+                self.vesting_schedule[i] = VestingEntry {
+                    amount: monthly_amount,
+                    release_time,
+                    claimed: false,
+                };
+            } else {
+                self.vesting_schedule[i].amount = self.vesting_schedule[i]
+                    .amount
+                    .checked_add(monthly_amount)
+                    .ok_or(PresaleError::MathOverflow)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reverses an `add_vesting` call with the same arguments. Only valid
+    /// while none of the affected entries have been claimed.
+    pub fn remove_vesting(
+        &mut self,
+        token_amount: u64,
+        launch_percentage: u8,
+        monthly_percentage: u8,
+    ) -> Result<()> {
+        let launch_amount = token_amount
+            .checked_mul(launch_percentage as u64)
+            .and_then(|a| a.checked_div(100))
+            .ok_or(PresaleError::MathOverflow)?;
+
+        let monthly_amount = token_amount
+            .checked_mul(monthly_percentage as u64)
+            .and_then(|a| a.checked_div(100))
+            .ok_or(PresaleError::MathOverflow)?;
+
+        for (i, entry) in self.vesting_schedule.iter_mut().enumerate() {
+            let amount = if i == 0 { launch_amount } else { monthly_amount };
+            require!(!entry.claimed || amount == 0, PresaleError::AlreadyClaimed);
+            entry.amount = entry
+                .amount
+                .checked_sub(amount)
+                .ok_or(PresaleError::MathOverflow)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Running totals kept so the sale can be reported without scanning every
+/// purchase account. Registered SPL mints are tracked on their registry entry.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct SaleStats {
+    pub raised_sol: u64, // kept after refunds
+    pub raised_usdc: u64,
+    pub phase_raised_sol: [u64; PHASE_COUNT], // fixed-price phase purchases only
+    pub phase_raised_usdc: [u64; PHASE_COUNT],
+    pub unique_buyers: u64,
+    pub total_claimed: u64, // vested tokens paid out of the treasury
+    pub refunded_sol: u64,
+    pub refunded_usdc: u64,
+}
+
+impl SaleStats {
+    pub const SIZE: usize = 8 + // raised_sol
+        8 +  // raised_usdc
+        (PHASE_COUNT * 8) + // phase_raised_sol
+        (PHASE_COUNT * 8) + // phase_raised_usdc
+        8 +  // unique_buyers
+        8 +  // total_claimed
+        8 +  // refunded_sol
+        8;   // refunded_usdc
+
+    pub fn record_raised(&mut self, payment_type: PaymentType, amount: u64) -> Result<()> {
+        let raised = match payment_type {
+            PaymentType::Sol => &mut self.raised_sol,
+            PaymentType::Usdc => &mut self.raised_usdc,
+            PaymentType::Spl => return Ok(()),
+        };
+        *raised = raised.checked_add(amount).ok_or(PresaleError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Splits `received_amount` across the phases of `fills` in proportion to
+    /// their list price; the last fill takes the rounding remainder.
+    pub fn record_phase_raised(
+        &mut self,
+        payment_type: PaymentType,
+        fills: &[PhaseFill],
+        received_amount: u64,
+    ) -> Result<()> {
+        let phase_raised = match payment_type {
+            PaymentType::Sol => &mut self.phase_raised_sol,
+            PaymentType::Usdc => &mut self.phase_raised_usdc,
+            PaymentType::Spl => return Ok(()),
+        };
+        let total: u128 = fills.iter().map(|fill| fill.payment_amount as u128).sum();
+        let mut remaining = received_amount;
+        for (i, fill) in fills.iter().enumerate() {
+            let share = if i + 1 == fills.len() || total == 0 {
+                remaining
+            } else {
+                (fill.payment_amount as u128 * received_amount as u128 / total) as u64
+            };
+            remaining -= share;
+            let raised = &mut phase_raised[fill.phase as usize];
+            *raised = raised.checked_add(share).ok_or(PresaleError::MathOverflow)?;
+        }
+        Ok(())
+    }
+
+    pub fn record_refund(&mut self, payment_type: PaymentType, amount: u64) -> Result<()> {
+        let refunded = match payment_type {
+            PaymentType::Sol => &mut self.refunded_sol,
+            PaymentType::Usdc => &mut self.refunded_usdc,
+            PaymentType::Spl => return Ok(()),
+        };
+        *refunded = refunded.checked_add(amount).ok_or(PresaleError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Counts a wallet the first time it is credited purchased tokens.
+    pub fn record_buyer(&mut self, previously_purchased: u64, token_amount: u64) -> Result<()> {
+        if previously_purchased == 0 && token_amount > 0 {
+            self.unique_buyers = self
+                .unique_buyers
+                .checked_add(1)
+                .ok_or(PresaleError::MathOverflow)?;
+        }
+        Ok(())
+    }
+
+    pub fn record_claim(&mut self, amount: u64) -> Result<()> {
+        self.total_claimed = self
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(PresaleError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Portion of a purchase filled from a single phase.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct PhaseFill {
+    pub phase: u8,
+    pub token_amount: u64,
+    pub price: u64, // payment base units per whole sale token, rounded up
+    pub payment_amount: u64,
+}
+
+/// Registry entry for an additional SPL payment token (USDT, PYUSD, ...).
+#[account]
+pub struct PaymentMintConfig {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub decimals: u8,
+    pub usd_pegged: bool, // priced from each phase's `price_usdc` instead of `prices`
+    pub prices: [u64; PHASE_COUNT], // numerators over each phase's `price_denominator`
+    pub is_enabled: bool,
+    pub total_received: u64,
+    pub bump: u8,
+}
+
+impl PaymentMintConfig {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // mint
+        32 + // vault
+        1 +  // decimals
+        1 +  // usd_pegged
+        (PHASE_COUNT * 8) + // prices
+        1 +  // is_enabled
+        8 +  // total_received
+        1;   // bump
+
+    /// Curved phases keep their USDC start/end ratio for non-pegged mints.
+    pub fn phase_price_range(&self, config: &PresaleConfig, phase_index: usize) -> Result<PriceRange> {
+        let phase = &config.phases[phase_index];
+        if self.usd_pegged {
+            return config
+                .phase_price_range(phase_index, PaymentType::Usdc)?
+                .map(|price| price.rescale(config.usdc_decimals, self.decimals));
+        }
+
+        let start = Price::new(
+            self.prices[phase_index] as u128,
+            phase.price_denominator as u128,
+        )?;
+        if phase.curve == PriceCurve::Flat {
+            return Ok(PriceRange::flat(start));
+        }
+        Ok(PriceRange {
+            start,
+            end: start.scale(phase.end_price_usdc as u128, phase.price_usdc as u128)?,
+        })
+    }
+}
+
+/// SOL/USD rate pushed by the authority or a rate updater, for clusters
+/// without a price feed.
+#[account]
+pub struct ExchangeRate {
+    pub rate_updater: Pubkey,
+    pub rate: u64, // USD per SOL, with `EXCHANGE_RATE_DECIMALS` decimals
+    pub updated_at: i64,
+    pub max_deviation_bps: u16, // largest change the rate updater may push at once
+    pub max_age: i64, // in seconds
+    pub min_update_interval: i64, // seconds the rate updater must wait between pushes
+    pub bump: u8,
+}
+
+impl ExchangeRate {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // rate_updater
+        8 +  // rate
+        8 +  // updated_at
+        2 +  // max_deviation_bps
+        8 +  // max_age
+        8 +  // min_update_interval
+        1;   // bump
+
+    /// The rate in the same shape as a feed price, so both share one conversion path.
+    pub fn to_oracle_price(&self) -> OraclePrice {
+        OraclePrice {
+            price: self.rate as i64,
+            conf: 0,
+            expo: -(EXCHANGE_RATE_DECIMALS as i32),
+            publish_time: self.updated_at,
+        }
+    }
+
+    pub fn validate(&self, current_time: i64) -> Result<OraclePrice> {
+        let price = self.to_oracle_price();
+        price.validate(current_time, self.max_age, 0)?;
+        Ok(price)
+    }
+}
+
+/// Descending-price round run alongside the fixed-price phases. Prices are
+/// lamports per `price_denominator` sale token base units.
+#[account]
+pub struct DutchAuction {
+    pub round_id: u64,
+    pub ceiling_price: u64,
+    pub floor_price: u64,
+    pub price_denominator: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub price_step: i64, // seconds between price drops
+    pub tokens_allocated: u64,
+    pub tokens_sold: u64,
+    pub uniform_clearing: bool, // settle every bid at the clearing price and refund the difference
+    pub last_price: u64, // price paid by the most recent bid
+    pub clearing_price: u64, // set at settlement
+    pub is_settled: bool,
+    pub bump: u8,
+}
+
+impl DutchAuction {
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // round_id
+        8 +  // ceiling_price
+        8 +  // floor_price
+        8 +  // price_denominator
+        8 +  // start_time
+        8 +  // end_time
+        8 +  // price_step
+        8 +  // tokens_allocated
+        8 +  // tokens_sold
+        1 +  // uniform_clearing
+        8 +  // last_price
+        8 +  // clearing_price
+        1 +  // is_settled
+        1;   // bump
+
+    /// Price at `current_time`. It falls from the ceiling to the floor in
+    /// steps of `price_step` seconds, rounding in the program's favour.
+    pub fn current_price(&self, current_time: i64) -> Result<u64> {
+        let duration = self
+            .end_time
+            .checked_sub(self.start_time)
+            .ok_or(PresaleError::MathOverflow)?;
+        let elapsed = current_time.saturating_sub(self.start_time).clamp(0, duration);
+        let stepped = elapsed - elapsed % self.price_step;
+
+        let drop = (self.ceiling_price - self.floor_price) as u128 * stepped as u128 / duration as u128;
+        Ok(self.ceiling_price - drop as u64)
+    }
+
+    pub fn payment_for(&self, price: u64, token_amount: u64) -> Result<u64> {
+        Price::new(price as u128, self.price_denominator as u128)?.payment_for(token_amount)
+    }
+
+    pub fn is_active(&self, current_time: i64) -> bool {
+        !self.is_settled
+            && current_time >= self.start_time
+            && current_time <= self.end_time
+            && self.tokens_sold < self.tokens_allocated
+    }
+
+    pub fn has_ended(&self, current_time: i64) -> bool {
+        current_time > self.end_time || self.tokens_sold >= self.tokens_allocated
+    }
+}
+
+/// A buyer's commitments to the Dutch auction.
+#[account]
+pub struct AuctionBid {
+    pub bidder: Pubkey,
+    pub token_amount: u64,
+    pub payment_amount: u64, // lamports paid at commit prices
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+impl AuctionBid {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // bidder
+        8 +  // token_amount
+        8 +  // payment_amount
+        1 +  // claimed
+        1;   // bump
+}
+
+/// Commit-then-settle round: deposits are uncapped during the window and,
+/// when oversubscribed, every depositor is filled pro-rata against
+/// `tokens_allocated` and refunded the rest.
+#[account]
+pub struct FairLaunch {
+    pub round_id: u64,
+    pub price_sol: u64, // Lamports per `price_denominator` sale token base units
+    pub price_usdc: u64, // Micro-USDC per `price_denominator` sale token base units
+    pub price_denominator: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub tokens_allocated: u64,
+    pub total_demand: u64, // tokens the deposits would buy at full fill
+    pub total_sol_deposited: u64,
+    pub total_usdc_deposited: u64,
+    pub tokens_filled: u64, // min(total_demand, tokens_allocated), set at settlement
+    pub is_settled: bool,
+    pub bump: u8,
+}
+
+impl FairLaunch {
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // round_id
+        8 +  // price_sol
+        8 +  // price_usdc
+        8 +  // price_denominator
+        8 +  // start_time
+        8 +  // end_time
+        8 +  // tokens_allocated
+        8 +  // total_demand
+        8 +  // total_sol_deposited
+        8 +  // total_usdc_deposited
+        8 +  // tokens_filled
+        1 +  // is_settled
+        1;   // bump
+
+    pub fn is_open(&self, current_time: i64) -> bool {
+        current_time >= self.start_time && current_time <= self.end_time
+    }
+
+    /// Tokens a deposit would buy at a full fill, rounded down.
+    pub fn demand_for(&self, payment_type: PaymentType, amount: u64) -> Result<u64> {
+        let price = match payment_type {
+            PaymentType::Sol => self.price_sol,
+            PaymentType::Usdc => self.price_usdc,
+            PaymentType::Spl => return err!(PresaleError::InvalidPaymentType),
+        };
+        Price::new(price as u128, self.price_denominator as u128)?.tokens_for(amount)
+    }
+
+    /// Tokens delivered for `demand`, rounded down.
+    pub fn filled_tokens(&self, demand: u64) -> Result<u64> {
+        if self.total_demand == 0 {
+            return Ok(0);
+        }
+        Price::new(self.total_demand as u128, self.tokens_filled as u128)?.tokens_for(demand)
+    }
+
+    /// Part of a deposit that pays for the filled tokens, rounded up.
+    pub fn used_deposit(&self, deposit: u64) -> Result<u64> {
+        if self.total_demand == 0 {
+            return Ok(0);
+        }
+        Price::new(self.tokens_filled as u128, self.total_demand as u128)?.payment_for(deposit)
+    }
+}
+
+/// A depositor's position in the fair launch.
+#[account]
+pub struct FairLaunchDeposit {
+    pub depositor: Pubkey,
+    pub sol_amount: u64,
+    pub usdc_amount: u64,
+    pub demand: u64, // tokens requested across both currencies
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+impl FairLaunchDeposit {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // depositor
+        8 +  // sol_amount
+        8 +  // usdc_amount
+        8 +  // demand
+        1 +  // claimed
+        1;   // bump
+}
+
+/// Ticket-based round: each registrant pays `ticket_price` lamports, a
+/// committed seed picks `winner_count` winners, and everyone else is refunded.
+#[account]
+pub struct Lottery {
+    pub round_id: u64,
+    pub seed_commitment: [u8; 32], // sha256 of the seed, published before registration
+    pub entrants_hash: [u8; 32], // running hash of every registrant
+    pub randomness: [u8; 32], // set when the seed is revealed
+    pub ticket_price: u64, // lamports
+    pub tokens_per_winner: u64,
+    pub winner_count: u32,
+    pub ticket_count: u32,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub reveal_deadline: i64, // the lottery can be cancelled if the seed is not revealed by then
+    pub is_revealed: bool,
+    pub is_cancelled: bool, // every ticket is refunded
+    pub bump: u8,
+}
+
+impl Lottery {
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // round_id
+        32 + // seed_commitment
+        32 + // entrants_hash
+        32 + // randomness
+        8 +  // ticket_price
+        8 +  // tokens_per_winner
+        4 +  // winner_count
+        4 +  // ticket_count
+        8 +  // start_time
+        8 +  // end_time
+        8 +  // reveal_deadline
+        1 +  // is_revealed
+        1 +  // is_cancelled
+        1;   // bump
+
+    pub fn is_open(&self, current_time: i64) -> bool {
+        current_time >= self.start_time && current_time <= self.end_time
+    }
+
+    /// Tokens reserved for the prizes when the lottery was created.
+    pub fn tokens_allocated(&self) -> u64 {
+        self.tokens_per_winner * self.winner_count as u64
+    }
+}
+
+/// One wallet's ticket in the lottery.
+#[account]
+pub struct LotteryTicket {
+    pub owner: Pubkey,
+    pub ticket_index: u32, // registration order
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+impl LotteryTicket {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // owner
+        4 +  // ticket_index
+        1 +  // claimed
+        1;   // bump
+}
+
+/// A registered affiliate and the rewards booked for them.
+#[account]
+pub struct Referrer {
+    pub wallet: Pubkey,
+    pub is_active: bool,
+    pub referred_purchases: u64,
+    pub referred_tokens: u64,
+    pub pending_tokens: u64, // vested into the referrer's purchase record at claim
+    pub pending_sol: u64,
+    pub pending_usdc: u64,
+    pub bump: u8,
+}
+
+impl Referrer {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // wallet
+        1 +  // is_active
+        8 +  // referred_purchases
+        8 +  // referred_tokens
+        8 +  // pending_tokens
+        8 +  // pending_sol
+        8 +  // pending_usdc
+        1;   // bump
+}
+
+/// A campaign discount code, stored under the sha256 of the code.
+#[account]
+pub struct PromoCode {
+    pub code_hash: [u8; 32],
+    pub discount_bps: u16,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub expires_at: i64,
+    pub phase: Option<u8>, // only valid for orders starting in this phase
+    pub is_active: bool,
+    pub bump: u8,
+}
+
+impl PromoCode {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // code_hash
+        2 +  // discount_bps
+        4 +  // max_uses
+        4 +  // uses
+        8 +  // expires_at
+        2 +  // phase
+        1 +  // is_active
+        1;   // bump
+
+    pub fn hash_code(code: &str) -> [u8; 32] {
+        hashv(&[code.as_bytes()]).to_bytes()
+    }
+
+    /// Checks the preimage and whether the code can still be used for an
+    /// order starting in `phase_index`.
+    pub fn validate(&self, code: &str, phase_index: usize, current_time: i64) -> Result<()> {
+        require!(
+            self.is_active && Self::hash_code(code) == self.code_hash,
+            PresaleError::InvalidPromoCode
+        );
+        require!(current_time <= self.expires_at, PresaleError::PromoCodeExpired);
+        require!(self.uses < self.max_uses, PresaleError::PromoCodeExhausted);
+        if let Some(phase) = self.phase {
+            require!(phase as usize == phase_index, PresaleError::InvalidPromoCode);
+        }
+        Ok(())
+    }
+}
+
+/// Tokens granted by the authority for an off-chain payment.
+#[account]
+pub struct OtcGrant {
+    pub beneficiary: Pubkey,
+    pub reference: [u8; 32], // off-chain payment reference, e.g. a wire id
+    pub token_amount: u64,
+    pub granted_at: i64,
+    pub is_revoked: bool,
+    pub bump: u8,
+}
+
+impl OtcGrant {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // beneficiary
+        32 + // reference
+        8 +  // token_amount
+        8 +  // granted_at
+        1 +  // is_revoked
+        1;   // bump
+}
+
+/// A team, advisor or partner allocation with its own cliff and duration.
+#[account]
+pub struct VestingGrant {
+    pub beneficiary: Pubkey,
+    pub grant_id: u64, // tells apart a beneficiary's grants
+    pub total_amount: u64,
+    pub start_time: i64,
+    pub cliff_months: u8, // months before the first release, which includes them
+    pub vesting_months: u8,
+    pub revocable: bool,
+    pub is_revoked: bool,
+    pub schedule: [VestingEntry; MAX_GRANT_MONTHS],
+    pub bump: u8,
+}
+
+impl VestingGrant {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // beneficiary
+        8 +  // grant_id
+        8 +  // total_amount
+        8 +  // start_time
+        1 +  // cliff_months
+        1 +  // vesting_months
+        1 +  // revocable
+        1 +  // is_revoked
+        (MAX_GRANT_MONTHS * std::mem::size_of::<VestingEntry>()) + // schedule
+        1;   // bump
+
+    /// Releases `total_amount` in equal monthly parts over `vesting_months`
+    /// from `start_time`. Parts due before the cliff are released at the
+    /// cliff; the last part takes the rounding remainder.
+    pub fn build_schedule(&mut self) -> Result<()> {
+        let months = self.vesting_months as u64;
+        let monthly_amount = self.total_amount / months;
+        let first = (self.cliff_months.max(1) - 1) as usize;
+
+        self.schedule = [VestingEntry::default(); MAX_GRANT_MONTHS];
+        for i in 0..self.vesting_months as usize {
+            let release_time = self
+                .start_time
+                .checked_add(
+                    (i as i64 + 1)
+                        .checked_mul(MONTH_SECONDS)
+                        .ok_or(PresaleError::MathOverflow)?,
+                )
+                .ok_or(PresaleError::MathOverflow)?;
+            let index = i.max(first);
+            self.schedule[index].release_time = release_time.max(self.schedule[index].release_time);
+            self.schedule[index].amount += monthly_amount;
+        }
+        let last = self.vesting_months as usize - 1;
+        self.schedule[last].amount += self.total_amount - monthly_amount * months;
+        Ok(())
+    }
+
+    /// Amount not yet released at `current_time`.
+    pub fn unvested_amount(&self, current_time: i64) -> u64 {
+        self.schedule
+            .iter()
+            .filter(|entry| current_time < entry.release_time)
+            .map(|entry| entry.amount)
+            .sum()
+    }
+}
+
+/// Off-chain settled allocations committed as a single Merkle root.
+#[account]
+pub struct MerkleDistributor {
+    pub round_id: u64,
+    pub root: [u8; 32],
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub claim_count: u64,
+    pub bump: u8,
+}
+
+impl MerkleDistributor {
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // round_id
+        32 + // root
+        8 +  // total_amount
+        8 +  // claimed_amount
+        8 +  // claim_count
+        1;   // bump
+}
+
+/// Marks a Merkle leaf as claimed.
+#[account]
+pub struct MerkleClaim {
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub claimed_at: i64,
+    pub bump: u8,
+}
+
+impl MerkleClaim {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // wallet
+        8 +  // amount
+        8 +  // claimed_at
+        1;   // bump
+}
+
+/// Record of a single purchase, stored at (`purchase_receipt`, buyer, index)
+/// where `index` counts the buyer's receipts from zero.
+#[account]
+pub struct PurchaseReceipt {
+    pub buyer: Pubkey,
+    pub index: u64,
+    pub timestamp: i64,
+    pub phase: u8, // phase the order started in
+    pub phases_filled: u8,
+    pub price: u64, // spot price of the first fill, per whole sale token
+    pub payment_type: PaymentType,
+    pub payment_mint: Pubkey,
+    pub amount_paid: u64, // what arrived in the vault
+    pub tokens_received: u64, // purchased tokens, without bonuses
+    pub bonus_tokens: u64, // phase and buyer referral bonuses
+    pub client_order_id: Option<[u8; 32]>,
+    pub bump: u8,
+}
+
+impl PurchaseReceipt {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // buyer
+        8 +  // index
+        8 +  // timestamp
+        1 +  // phase
+        1 +  // phases_filled
+        8 +  // price
+        1 +  // payment_type
+        32 + // payment_mint
+        8 +  // amount_paid
+        8 +  // tokens_received
+        8 +  // bonus_tokens
+        (1 + 32) + // client_order_id
+        1;   // bump
+}
+
+/// What a referred purchase earns.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReferralReward {
+    #[default]
+    None,
+    /// Extra vested tokens for the buyer
+    BuyerTokens,
+    /// Vested tokens for the referrer
+    ReferrerTokens,
+    /// Share of SOL / USDC payments for the referrer
+    Commission,
+}
+
+/// How a phase's price moves between its start and end price.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum PriceCurve {
+    /// Fixed `price_sol` / `price_usdc` for the whole phase
+    #[default]
+    Flat,
+    /// Linear from `start_time` to `end_time`
+    Time,
+    /// Linear in `tokens_sold / tokens_allocated`
+    Supply,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
 pub enum PaymentType {
     Sol,
     Usdc,
+    Spl,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::*;
+
+    #[test]
+    fn sold_out_phase_rolls_over_to_the_next() {
+        let mut config = ConfigBuilder::new().auto_rollover().sold(0, PHASE_TOKENS).build();
+
+        config.sync_phases(40).unwrap();
+        assert_eq!(config.phases[0].end_time, 39);
+        assert_eq!(config.phases[1].start_time, 40);
+        assert_eq!(config.phases[1].end_time, 139);
+        // Later windows keep their length and spacing
+        assert_eq!(config.phases[2].start_time, 140);
+        assert_eq!(config.get_current_phase(40), Some(1));
+    }
+
+    #[test]
+    fn sold_out_rollover_cascades_through_sold_out_phases() {
+        let mut config = ConfigBuilder::new()
+            .auto_rollover()
+            .sold(0, PHASE_TOKENS)
+            .sold(1, PHASE_TOKENS)
+            .build();
+
+        config.sync_phases(40).unwrap();
+        assert_eq!(config.get_current_phase(40), Some(2));
+        assert_eq!(config.phases[2].start_time, 40);
+    }
+
+    #[test]
+    fn sold_out_phase_stays_open_without_rollover() {
+        let mut config = ConfigBuilder::new().sold(0, PHASE_TOKENS).build();
+
+        config.sync_phases(40).unwrap();
+        assert_eq!(config.get_current_phase(40), Some(0));
+        assert_eq!(config.phases[1].start_time, PHASE_LENGTH);
+    }
+
+    #[test]
+    fn unsold_allocation_carries_over_once_a_phase_expires() {
+        let mut config = ConfigBuilder::new().carry_over_unsold().sold(0, 400).build();
+
+        // Still running: nothing moves
+        config.sync_phases(50).unwrap();
+        assert_eq!(config.phases[0].tokens_allocated, PHASE_TOKENS);
+
+        config.sync_phases(150).unwrap();
+        assert_eq!(config.phases[0].tokens_allocated, 400);
+        assert_eq!(config.phases[1].tokens_allocated, PHASE_TOKENS + 600);
+        assert_eq!(config.phases[2].tokens_allocated, PHASE_TOKENS);
+    }
+
+    fn usdc_prices(config: &PresaleConfig) -> impl Fn(usize) -> Result<PriceRange> + '_ {
+        move |index| config.phase_price_range(index, PaymentType::Usdc)
+    }
+
+    fn fill_summary(fills: &[PhaseFill]) -> Vec<(u8, u64, u64)> {
+        fills
+            .iter()
+            .map(|fill| (fill.phase, fill.token_amount, fill.payment_amount))
+            .collect()
+    }
+
+    #[test]
+    fn order_past_the_phase_end_is_split_across_phases() {
+        let config = ConfigBuilder::new().auto_rollover().sold(0, 900).build();
+
+        let fills = config.plan_purchase(0, 300, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 100, 1_000), (1, 200, 4_000)]);
+    }
+
+    #[test]
+    fn split_order_skips_sold_out_phases() {
+        let config = ConfigBuilder::new()
+            .auto_rollover()
+            .sold(0, 900)
+            .sold(1, PHASE_TOKENS)
+            .build();
+
+        let fills = config.plan_purchase(0, 300, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 100, 1_000), (2, 200, 6_000)]);
+    }
+
+    #[test]
+    fn order_past_the_phase_end_fails_without_rollover() {
+        let config = ConfigBuilder::new().sold(0, 900).build();
+
+        assert!(config.plan_purchase(0, 300, 0, usdc_prices(&config)).is_err());
+        let fills = config.plan_purchase(0, 100, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 100, 1_000)]);
+    }
+
+    #[test]
+    fn order_larger_than_the_remaining_phases_fails() {
+        let config = ConfigBuilder::new().auto_rollover().sold(PHASE_COUNT - 1, 900).build();
+
+        assert!(config.plan_purchase(PHASE_COUNT - 1, 101, 0, usdc_prices(&config)).is_err());
+    }
+
+    fn spend_limits(config: &mut PresaleConfig) {
+        config.trim_to_limits = true;
+        config.max_purchase_per_transaction = 500;
+        config.max_purchase_per_wallet = 1_000;
+    }
+
+    #[test]
+    fn spend_buys_the_largest_affordable_amount() {
+        let config = ConfigBuilder::new().build();
+
+        // The 5 left over cannot buy a whole token and is not charged
+        let fills = config.plan_spend(0, 1_005, u64::MAX, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 100, 1_000)]);
+    }
+
+    #[test]
+    fn spend_is_trimmed_to_the_remaining_wallet_allocation() {
+        let mut config = ConfigBuilder::new().build();
+        spend_limits(&mut config);
+        let mut user_purchase: UserPurchase = zeroed();
+        user_purchase.total_purchased = 900;
+
+        let max_tokens = config.spend_token_cap(&user_purchase);
+        assert_eq!(max_tokens, 100);
+        // Only the trimmed order is charged; the rest of the spend is never taken
+        let fills = config.plan_spend(0, 5_000, max_tokens, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 100, 1_000)]);
+
+        user_purchase.total_purchased = 1_200;
+        assert_eq!(config.spend_token_cap(&user_purchase), 0);
+        assert!(config.plan_spend(0, 5_000, 0, 0, usdc_prices(&config)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn spend_is_trimmed_to_the_per_transaction_cap() {
+        let mut config = ConfigBuilder::new().build();
+        spend_limits(&mut config);
+        let user_purchase: UserPurchase = zeroed();
+
+        let max_tokens = config.spend_token_cap(&user_purchase);
+        assert_eq!(max_tokens, 500);
+        let fills = config.plan_spend(0, 8_000, max_tokens, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 500, 5_000)]);
+
+        // Without trimming the whole spend is planned and the limits reject it
+        config.trim_to_limits = false;
+        let max_tokens = config.spend_token_cap(&user_purchase);
+        let fills = config.plan_spend(0, 8_000, max_tokens, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 800, 8_000)]);
+    }
+
+    #[test]
+    fn spend_is_capped_by_the_phase_supply() {
+        let config = ConfigBuilder::new().auto_rollover().sold(0, 900).build();
+
+        // Phase 0 has 100 left; the rest of the spend moves to phase 1's price
+        let fills = config.plan_spend(0, 3_000, u64::MAX, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 100, 1_000), (1, 100, 2_000)]);
+
+        let config = ConfigBuilder::new().sold(0, 900).build();
+        let fills = config.plan_spend(0, 3_000, u64::MAX, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 100, 1_000)]);
+    }
+
+    #[test]
+    fn spend_too_small_for_one_token_buys_nothing() {
+        let config = ConfigBuilder::new().build();
+
+        assert!(config.plan_spend(0, 9, u64::MAX, 0, usdc_prices(&config)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn time_curve_prices_by_elapsed_time() {
+        let mut config = ConfigBuilder::new().build();
+        // 10 to 30 micro-USDC per token across the first phase
+        config.phases[0].curve = PriceCurve::Time;
+        config.phases[0].end_price_usdc = 30_000_000_000;
+        let phase = config.phases[0];
+        let prices = config.phase_price_range(0, PaymentType::Usdc).unwrap();
+
+        assert_eq!(phase.payment_for(&prices, 10, 0).unwrap(), 100);
+        // 49 of 99 seconds in, 99 tokens cost 99 * 10 + 49 * 20
+        assert_eq!(phase.spot_price(&prices, 49).unwrap().payment_for(99).unwrap(), 1_970);
+        assert_eq!(phase.payment_for(&prices, 10, PHASE_LENGTH - 1).unwrap(), 300);
+        assert_eq!(phase.tokens_for(&prices, 300, u64::MAX, PHASE_LENGTH - 1).unwrap(), 10);
+    }
+
+    #[test]
+    fn supply_curve_prices_by_tokens_sold() {
+        let mut config = ConfigBuilder::new().sold(0, PHASE_TOKENS / 2).build();
+        config.phases[0].curve = PriceCurve::Supply;
+        config.phases[0].end_price_usdc = 30_000_000_000;
+        let phase = config.phases[0];
+        let prices = config.phase_price_range(0, PaymentType::Usdc).unwrap();
+
+        // The second half runs from 20 to 30, averaging 25
+        assert_eq!(phase.payment_for(&prices, PHASE_TOKENS / 2, 0).unwrap(), 12_500);
+        assert_eq!(phase.tokens_for(&prices, 12_500, u64::MAX, 0).unwrap(), PHASE_TOKENS / 2);
+    }
+
+    #[test]
+    fn round_reservations_are_withheld_from_phase_sales() {
+        let mut config = ConfigBuilder::new().build();
+        let total = config.total_tokens_for_sale;
+
+        config.reserve_round_tokens(total - 100).unwrap();
+        assert_eq!(config.unsold_supply(), 100);
+        assert!(config.reserve_round_tokens(101).is_err());
+        // Spend-exact orders are capped to what is left, even without trimming
+        assert_eq!(config.spend_token_cap(&zeroed()), 100);
+
+        // Settling sells part of the round and returns the rest
+        config.release_round_tokens(total - 100, 300).unwrap();
+        assert_eq!(config.round_tokens_reserved, 0);
+        assert_eq!(config.tokens_sold, 300);
+        assert_eq!(config.unsold_supply(), total - 300);
+    }
+
+    #[test]
+    fn auction_price_falls_in_steps_to_the_floor() {
+        let mut auction: DutchAuction = zeroed();
+        auction.ceiling_price = 1_000;
+        auction.floor_price = 400;
+        auction.start_time = 100;
+        auction.end_time = 700;
+        auction.price_step = 60;
+
+        assert_eq!(auction.current_price(0).unwrap(), 1_000);
+        assert_eq!(auction.current_price(159).unwrap(), 1_000);
+        assert_eq!(auction.current_price(160).unwrap(), 940);
+        assert_eq!(auction.current_price(400).unwrap(), 700);
+        assert_eq!(auction.current_price(10_000).unwrap(), 400);
+    }
+
+    #[test]
+    fn oversubscribed_fair_launch_fills_every_deposit_pro_rata() {
+        let mut fair_launch: FairLaunch = zeroed();
+        fair_launch.price_sol = 10;
+        fair_launch.price_denominator = 1;
+        fair_launch.tokens_allocated = 1_000;
+        assert_eq!(fair_launch.demand_for(PaymentType::Sol, 10_005).unwrap(), 1_000);
+
+        // Three times the allocation is demanded
+        fair_launch.total_demand = 3_000;
+        fair_launch.tokens_filled = fair_launch.total_demand.min(fair_launch.tokens_allocated);
+        assert_eq!(fair_launch.filled_tokens(1_000).unwrap(), 333);
+        assert_eq!(fair_launch.filled_tokens(3_000).unwrap(), 1_000);
+        // A third of each deposit is used, rounded up, and the rest refunded
+        assert_eq!(fair_launch.used_deposit(10_000).unwrap(), 3_334);
+    }
+
+    fn payment_mint(decimals: u8, usd_pegged: bool) -> PaymentMintConfig {
+        let mut entry: PaymentMintConfig = zeroed();
+        entry.decimals = decimals;
+        entry.usd_pegged = usd_pegged;
+        entry.is_enabled = true;
+        entry
+    }
+
+    #[test]
+    fn payment_mints_are_classified_by_mint() {
+        let mut config = ConfigBuilder::new().build();
+        config.usdc_mint = Pubkey::new_unique();
+
+        assert_eq!(
+            config.payment_type_for(&anchor_spl::token::spl_token::native_mint::ID),
+            PaymentType::Sol
+        );
+        assert_eq!(config.payment_type_for(&config.usdc_mint), PaymentType::Usdc);
+        assert_eq!(config.payment_type_for(&Pubkey::new_unique()), PaymentType::Spl);
+    }
+
+    #[test]
+    fn pegged_mint_rescales_the_usdc_price_to_its_decimals() {
+        let config = ConfigBuilder::new().build();
+
+        // 10 micro-USDC per token is 1_000 base units of an 8-decimal stablecoin
+        let prices = payment_mint(8, true).phase_price_range(&config, 0).unwrap();
+        assert_eq!(prices.start.payment_for(1).unwrap(), 1_000);
+
+        // ... and 0.1 base units of a 4-decimal one, rounded up per order
+        let prices = payment_mint(4, true).phase_price_range(&config, 0).unwrap();
+        assert_eq!(prices.start.payment_for(100).unwrap(), 10);
+        assert_eq!(prices.start.payment_for(1).unwrap(), 1);
+    }
+
+    #[test]
+    fn unpegged_mint_uses_its_own_prices_and_curve_shape() {
+        let mut config = ConfigBuilder::new().build();
+        let mut entry = payment_mint(9, false);
+        entry.prices[0] = 7_000_000_000;
+        entry.prices[1] = 9_000_000_000;
+
+        assert_eq!(entry.phase_price_range(&config, 1).unwrap(), PriceRange::flat(Price::new(9, 1).unwrap()));
+        assert!(config.phase_price(1, PaymentType::Spl).is_err());
+
+        // A curved phase keeps the USDC end/start ratio
+        config.phases[0].curve = PriceCurve::Supply;
+        config.phases[0].end_price_usdc = 20_000_000_000;
+        let prices = entry.phase_price_range(&config, 0).unwrap();
+        assert_eq!(prices.start, Price::new(7, 1).unwrap());
+        assert_eq!(prices.end, Price::new(14, 1).unwrap());
+    }
+
+    #[test]
+    fn oracle_priced_sol_follows_the_usdc_price() {
+        let config = ConfigBuilder::new().build();
+        // $150 per SOL
+        let sol_usd = OraclePrice {
+            price: 15_000_000_000,
+            conf: 0,
+            expo: -8,
+            publish_time: 0,
+        };
+
+        // 30 micro-USDC per token in phase 3 is 200 lamports
+        let prices = config.payment_price(2, PaymentType::Sol, None, Some(&sol_usd)).unwrap();
+        assert_eq!(prices.start.payment_for(1).unwrap(), 200);
+        // Without a feed the fixed SOL price applies
+        let prices = config.payment_price(2, PaymentType::Sol, None, None).unwrap();
+        assert_eq!(prices.start.payment_for(1).unwrap(), 30);
+    }
+
+    fn discounted_config() -> PresaleConfig {
+        let mut config = ConfigBuilder::new().build();
+        config.discount_tiers[0] = DiscountTier { min_tokens: 100, discount_bps: 500 };
+        config.discount_tiers[1] = DiscountTier { min_tokens: 500, discount_bps: 1_000 };
+        config
+    }
+
+    #[test]
+    fn discount_is_the_largest_tier_reached() {
+        let mut config = discounted_config();
+
+        assert_eq!(config.discount_bps_for(99), 0);
+        assert_eq!(config.discount_bps_for(100), 500);
+        assert_eq!(config.discount_bps_for(499), 500);
+        assert_eq!(config.discount_bps_for(500), 1_000);
+        assert_eq!(config.discount_bps_for(u64::MAX), 1_000);
+
+        // Zeroed tiers never apply, even with no minimum
+        config.discount_tiers = [DiscountTier::default(); MAX_DISCOUNT_TIERS];
+        assert_eq!(config.discount_bps_for(u64::MAX), 0);
+    }
+
+    #[test]
+    fn discounted_orders_are_planned_at_the_discounted_price() {
+        let config = discounted_config();
+        let discounted = |discount_bps: u16| {
+            let config = &config;
+            move |index| {
+                config
+                    .phase_price_range(index, PaymentType::Usdc)?
+                    .map(|price| price.discount(discount_bps))
+            }
+        };
+
+        // 500 tokens at 10 less 10% cost 4_500
+        let discount_bps = config.discount_bps_for(500);
+        let fills = config.plan_purchase(0, 500, 0, discounted(discount_bps)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 500, 4_500)]);
+
+        // The same spend buys more tokens once discounted
+        let fills = config.plan_spend(0, 4_500, u64::MAX, 0, discounted(0)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 450, 4_500)]);
+        let fills = config.plan_spend(0, 4_500, u64::MAX, 0, discounted(1_000)).unwrap();
+        assert_eq!(fill_summary(&fills), vec![(0, 500, 4_500)]);
+    }
+
+    #[test]
+    fn treasury_must_cover_the_sale_and_pools_less_claims() {
+        let mut config = ConfigBuilder::new().build();
+        config.total_tokens_for_sale = 1_000;
+        config.referral_pool = 100;
+        assert_eq!(config.treasury_obligations().unwrap(), 1_100);
+        assert!(config.require_treasury_covers(1_099).is_err());
+        assert!(config.require_treasury_covers(1_100).is_ok());
+
+        // Claimed tokens have already left the treasury
+        config.stats.total_claimed = 300;
+        assert_eq!(config.treasury_obligations().unwrap(), 800);
+        assert!(config.require_treasury_covers(800).is_ok());
+
+        config.bonus_pool = 50;
+        config.reserved_allocation = 25;
+        assert_eq!(config.treasury_obligations().unwrap(), 875);
+    }
+
+    #[test]
+    fn bonus_is_earned_per_phase_and_rounded_down() {
+        let mut config = ConfigBuilder::new().auto_rollover().sold(0, PHASE_TOKENS - 99).build();
+        config.bonus_pool = 1_000;
+        config.phases[0].bonus_bps = 1_000;
+        config.phases[1].bonus_bps = 250;
+
+        // 99 tokens at 10% and 101 at 2.5%
+        let fills = config.plan_purchase(0, 200, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(config.bonus_for(&fills).unwrap(), 9 + 2);
+
+        // Phases without a bonus add nothing
+        let fills = config.plan_purchase(2, 500, 200, usdc_prices(&config)).unwrap();
+        assert_eq!(config.bonus_for(&fills).unwrap(), 0);
+    }
+
+    #[test]
+    fn bonus_is_capped_by_the_remaining_pool() {
+        let mut config = ConfigBuilder::new().build();
+        config.bonus_pool = 1_000;
+        config.bonus_tokens_granted = 980;
+        config.phases[0].bonus_bps = 1_000;
+
+        let fills = config.plan_purchase(0, 500, 0, usdc_prices(&config)).unwrap();
+        assert_eq!(config.bonus_for(&fills).unwrap(), 20);
+
+        config.bonus_tokens_granted = 1_000;
+        assert_eq!(config.bonus_for(&fills).unwrap(), 0);
+    }
+
+    fn promo_code() -> PromoCode {
+        PromoCode {
+            code_hash: PromoCode::hash_code("LAUNCH10"),
+            discount_bps: 1_000,
+            max_uses: 2,
+            uses: 0,
+            expires_at: 1_000,
+            phase: None,
+            is_active: true,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn promo_code_is_valid_until_it_expires() {
+        let promo = promo_code();
+
+        assert!(promo.validate("LAUNCH10", 3, 1_000).is_ok());
+        assert_eq!(
+            promo.validate("LAUNCH10", 3, 1_001).unwrap_err(),
+            PresaleError::PromoCodeExpired.into()
+        );
+    }
+
+    #[test]
+    fn promo_code_rejects_wrong_or_inactive_codes() {
+        let mut promo = promo_code();
+
+        assert_eq!(
+            promo.validate("launch10", 0, 0).unwrap_err(),
+            PresaleError::InvalidPromoCode.into()
+        );
+        promo.is_active = false;
+        assert_eq!(
+            promo.validate("LAUNCH10", 0, 0).unwrap_err(),
+            PresaleError::InvalidPromoCode.into()
+        );
+    }
+
+    #[test]
+    fn promo_code_is_limited_in_uses_and_phase() {
+        let mut promo = promo_code();
+
+        promo.uses = 2;
+        assert_eq!(
+            promo.validate("LAUNCH10", 0, 0).unwrap_err(),
+            PresaleError::PromoCodeExhausted.into()
+        );
+
+        promo.uses = 1;
+        promo.phase = Some(2);
+        assert!(promo.validate("LAUNCH10", 2, 0).is_ok());
+        assert_eq!(
+            promo.validate("LAUNCH10", 1, 0).unwrap_err(),
+            PresaleError::InvalidPromoCode.into()
+        );
+    }
+
+    #[test]
+    fn removing_a_grant_reverses_its_vesting() {
+        let mut user_purchase: UserPurchase = zeroed();
+        user_purchase.add_vesting(1_000, 100, 40, 30).unwrap();
+        user_purchase.add_vesting(500, 100, 40, 30).unwrap();
+
+        user_purchase.remove_vesting(500, 40, 30).unwrap();
+        let amounts: Vec<u64> = user_purchase.vesting_schedule.iter().map(|entry| entry.amount).collect();
+        assert_eq!(amounts, vec![400, 300, 300]);
+
+        // Claimed entries cannot be taken back
+        user_purchase.vesting_schedule[0].claimed = true;
+        assert_eq!(
+            user_purchase.remove_vesting(500, 40, 30).unwrap_err(),
+            PresaleError::AlreadyClaimed.into()
+        );
+    }
+
+    #[test]
+    fn grant_releases_everything_due_before_the_cliff_at_the_cliff() {
+        let mut grant: VestingGrant = zeroed();
+        grant.total_amount = 1_001;
+        grant.cliff_months = 2;
+        grant.vesting_months = 4;
+        grant.build_schedule().unwrap();
+
+        let released: Vec<(i64, u64)> = grant.schedule[..4]
+            .iter()
+            .map(|entry| (entry.release_time / MONTH_SECONDS, entry.amount))
+            .collect();
+        // The last month takes the rounding remainder
+        assert_eq!(released, vec![(0, 0), (2, 500), (3, 250), (4, 251)]);
+        assert_eq!(grant.unvested_amount(MONTH_SECONDS), 1_001);
+        assert_eq!(grant.unvested_amount(2 * MONTH_SECONDS), 501);
+        assert_eq!(grant.unvested_amount(4 * MONTH_SECONDS), 0);
+    }
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+// Shared state for unit tests. Accounts start zeroed, as `init` leaves them,
+// and `ConfigBuilder` lays out a sale that tests then adjust.
+
+pub const PHASE_LENGTH: i64 = 100;
+pub const PHASE_TOKENS: u64 = 1_000;
+
+/// Zeroed account of any type; `Option` fields read as `None`.
+pub fn zeroed<T: AccountDeserialize>() -> T {
+    T::try_deserialize_unchecked(&mut &[0u8; 4096][..]).unwrap()
+}
+
+/// Ten back-to-back phases of `PHASE_TOKENS` tokens, one every `PHASE_LENGTH`
+/// seconds, priced 10, 20, 30, ... per token base unit in both currencies.
+pub struct ConfigBuilder {
+    config: PresaleConfig,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        let mut config: PresaleConfig = zeroed();
+        config.total_tokens_for_sale = PHASE_TOKENS * PHASE_COUNT as u64;
+        config.token_decimals = 9;
+        config.usdc_decimals = 6;
+        for (index, phase) in config.phases.iter_mut().enumerate() {
+            // Prices are per whole token of 10^9 base units, so one base unit
+            // costs 10, 20, 30, ...
+            phase.price_sol = 10 * (index as u64 + 1) * 1_000_000_000;
+            phase.price_usdc = 10 * (index as u64 + 1) * 1_000_000_000;
+            phase.price_denominator = 1_000_000_000;
+            phase.start_time = index as i64 * PHASE_LENGTH;
+            phase.end_time = phase.start_time + PHASE_LENGTH - 1;
+            phase.tokens_allocated = PHASE_TOKENS;
+        }
+        Self { config }
+    }
+
+    pub fn auto_rollover(mut self) -> Self {
+        self.config.auto_rollover = true;
+        self
+    }
+
+    pub fn carry_over_unsold(mut self) -> Self {
+        self.config.carry_over_unsold = true;
+        self
+    }
+
+    /// Marks `tokens` of `phase` as already sold.
+    pub fn sold(mut self, phase: usize, tokens: u64) -> Self {
+        self.config.phases[phase].tokens_sold = tokens;
+        self.config.tokens_sold += tokens;
+        self
+    }
+
+    pub fn build(self) -> PresaleConfig {
+        self.config
+    }
+}
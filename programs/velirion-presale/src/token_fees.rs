@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::state::Mint as MintState;
+use anchor_spl::token_interface::TokenAccount;
+use crate::error::PresaleError;
+
+// Helpers for Token-2022 transfer-fee mints. Legacy SPL mints and Token-2022
+// mints without the extension behave as a zero fee.
+
+fn with_fee_config<T>(
+    mint: &AccountInfo,
+    default: T,
+    f: impl FnOnce(&TransferFeeConfig, u64) -> Option<T>,
+) -> Result<T> {
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(default);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) => {
+            let epoch = Clock::get()?.epoch;
+            Ok(f(fee_config, epoch).ok_or(PresaleError::MathOverflow)?)
+        }
+        Err(_) => Ok(default),
+    }
+}
+
+/// Fee withheld when `amount` is sent.
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    with_fee_config(mint, 0, |fee_config, epoch| {
+        fee_config.calculate_epoch_fee(epoch, amount)
+    })
+}
+
+/// Amount to send so that `net_amount` arrives after the transfer fee.
+pub fn gross_up_for_fee(mint: &AccountInfo, net_amount: u64) -> Result<u64> {
+    let fee = with_fee_config(mint, 0, |fee_config, epoch| {
+        fee_config.calculate_inverse_epoch_fee(epoch, net_amount)
+    })?;
+    Ok(net_amount
+        .checked_add(fee)
+        .ok_or(PresaleError::MathOverflow)?)
+}
+
+/// Current balance of an SPL or Token-2022 token account.
+pub fn token_balance(account: &AccountInfo) -> Result<u64> {
+    let data = TokenAccount::try_deserialize(&mut &account.data.borrow()[..])?;
+    Ok(data.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::entrypoint::SUCCESS;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use solana_sysvar::program_stubs::{set_syscall_stubs, SyscallStubs};
+    use anchor_spl::token_2022::spl_token_2022::extension::{
+        BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+    };
+
+    const FEE_EPOCH: u64 = 7;
+
+    // Off-chain there is no Clock sysvar; the fee schedule only needs the epoch
+    struct ClockStub;
+
+    impl SyscallStubs for ClockStub {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock {
+                epoch: FEE_EPOCH,
+                ..Clock::default()
+            };
+            unsafe { *(var_addr as *mut Clock) = clock };
+            SUCCESS
+        }
+    }
+
+    // 1% fee capped at 5_000 base units; a 2.5% schedule only starts after FEE_EPOCH
+    fn fee_mint_data() -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<MintState>(&[ExtensionType::TransferFeeConfig])
+            .unwrap();
+        let mut data = vec![0u8; len];
+        let mut state = StateWithExtensionsMut::<MintState>::unpack_uninitialized(&mut data).unwrap();
+        let fee_config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        fee_config.older_transfer_fee.epoch = 0.into();
+        fee_config.older_transfer_fee.maximum_fee = 5_000.into();
+        fee_config.older_transfer_fee.transfer_fee_basis_points = 100.into();
+        fee_config.newer_transfer_fee.epoch = (FEE_EPOCH + 1).into();
+        fee_config.newer_transfer_fee.maximum_fee = 5_000.into();
+        fee_config.newer_transfer_fee.transfer_fee_basis_points = 250.into();
+        state.base = MintState {
+            decimals: 6,
+            is_initialized: true,
+            ..MintState::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    fn plain_mint_data() -> Vec<u8> {
+        let mut data = vec![0u8; MintState::LEN];
+        MintState {
+            decimals: 6,
+            is_initialized: true,
+            ..MintState::default()
+        }
+        .pack_into_slice(&mut data);
+        data
+    }
+
+    fn with_mint<T>(owner: Pubkey, mut data: Vec<u8>, f: impl FnOnce(&AccountInfo) -> T) -> T {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mint = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        f(&mint)
+    }
+
+    #[test]
+    fn fee_mint_charges_the_current_epoch_fee() {
+        set_syscall_stubs(Box::new(ClockStub));
+
+        with_mint(anchor_spl::token_2022::ID, fee_mint_data(), |mint| {
+            assert_eq!(transfer_fee(mint, 10_000).unwrap(), 100);
+            // Large transfers are capped at the maximum fee
+            assert_eq!(transfer_fee(mint, 10_000_000).unwrap(), 5_000);
+        });
+    }
+
+    #[test]
+    fn fee_mint_grosses_up_so_the_net_amount_arrives() {
+        set_syscall_stubs(Box::new(ClockStub));
+
+        with_mint(anchor_spl::token_2022::ID, fee_mint_data(), |mint| {
+            let gross = gross_up_for_fee(mint, 9_900).unwrap();
+            assert_eq!(gross, 10_000);
+            assert_eq!(gross - transfer_fee(mint, gross).unwrap(), 9_900);
+
+            let gross = gross_up_for_fee(mint, 10_000_000).unwrap();
+            assert_eq!(gross, 10_005_000);
+        });
+    }
+
+    #[test]
+    fn mints_without_a_fee_extension_charge_nothing() {
+        with_mint(anchor_spl::token::ID, plain_mint_data(), |mint| {
+            assert_eq!(transfer_fee(mint, 10_000).unwrap(), 0);
+            assert_eq!(gross_up_for_fee(mint, 10_000).unwrap(), 10_000);
+        });
+        with_mint(anchor_spl::token_2022::ID, plain_mint_data(), |mint| {
+            assert_eq!(transfer_fee(mint, 10_000).unwrap(), 0);
+            assert_eq!(gross_up_for_fee(mint, 10_000).unwrap(), 10_000);
+        });
+    }
+}
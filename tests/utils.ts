@@ -1,6 +1,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import BN from "bn.js";
 import { Connection, PublicKey, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { createHash } from "crypto";
 
 /**
  * Helper function to wait for a specified number of seconds
@@ -50,10 +51,15 @@ export function createTestPhases(
     .map((_, i) => ({
       priceSol: new BN((100_000_000 + i * 10_000_000).toString()), // 0.1 SOL + 0.01 per phase
       priceUsdc: new BN((50_000 + i * 5_000).toString()), // $0.05 + $0.005 per phase
+      priceDenominator: new BN(0), // per whole token
       startTime: new BN(startTime + i * 86400 * phaseDurationDays),
       endTime: new BN(startTime + (i + 1) * 86400 * phaseDurationDays),
       tokensAllocated: toTokenAmount(1_000_000), // 1M tokens per phase
       tokensSold: new BN(0),
+      curve: { flat: {} },
+      endPriceSol: new BN(0),
+      endPriceUsdc: new BN(0),
+      bonusBps: 0,
     }));
 }
 
@@ -162,3 +168,90 @@ export function assertBNLessThan(
   }
 }
 
+
+/**
+ * Commitment for a lottery seed (sha256), for use with a fixed test seed
+ */
+export function lotterySeedCommitment(seed: Buffer): number[] {
+  return Array.from(createHash("sha256").update(seed).digest());
+}
+
+function sha256(...parts: Buffer[]): Buffer {
+  const hash = createHash("sha256");
+  parts.forEach((part) => hash.update(part));
+  return hash.digest();
+}
+
+/**
+ * Running hash of lottery registrants, as kept on-chain (lottery_draw.rs)
+ */
+export function lotteryEntrantsHash(entrants: PublicKey[]): Buffer {
+  return entrants.reduce(
+    (hash, entrant) => sha256(hash, entrant.toBuffer()),
+    Buffer.alloc(32)
+  );
+}
+
+/**
+ * Draw randomness from the revealed seed and the registrants
+ */
+export function lotteryDrawRandomness(seed: Buffer, entrants: PublicKey[]): Buffer {
+  return sha256(seed, lotteryEntrantsHash(entrants));
+}
+
+/**
+ * Off-chain replay of the lottery draw: whether the ticket at `ticketIndex`
+ * wins, using the same Feistel permutation as lottery_draw.rs
+ */
+export function lotteryIsWinner(
+  randomness: Buffer,
+  ticketIndex: number,
+  ticketCount: number,
+  winnerCount: number
+): boolean {
+  if (ticketCount <= winnerCount) {
+    return true;
+  }
+  const bits = 32 - Math.clz32(ticketCount - 1);
+  const halfBits = BigInt(Math.max(Math.ceil(bits / 2), 1));
+  const mask = (1n << halfBits) - 1n;
+
+  const feistel = (value: bigint): bigint => {
+    let left = value >> halfBits;
+    let right = value & mask;
+    for (let round = 0; round < 4; round++) {
+      const rightBytes = Buffer.alloc(8);
+      rightBytes.writeBigUInt64LE(right);
+      const digest = sha256(randomness, Buffer.from([round]), rightBytes);
+      const f = digest.readBigUInt64LE(0) & mask;
+      [left, right] = [right, left ^ f];
+    }
+    return (left << halfBits) | right;
+  };
+
+  // Cycle-walk back into 0..ticketCount
+  let value = BigInt(ticketIndex);
+  do {
+    value = feistel(value);
+  } while (value >= BigInt(ticketCount));
+  return value < BigInt(winnerCount);
+}
+
+/**
+ * Merkle distributor leaf for a (wallet, amount) allocation
+ */
+export function merkleLeaf(wallet: PublicKey, amount: BN): Buffer {
+  return createHash("sha256")
+    .update(Buffer.from([0]))
+    .update(wallet.toBuffer())
+    .update(amount.toArrayLike(Buffer, "le", 8))
+    .digest();
+}
+
+/**
+ * Merkle distributor inner node; the pair is sorted before hashing
+ */
+export function merkleParent(a: Buffer, b: Buffer): Buffer {
+  const [first, second] = Buffer.compare(a, b) <= 0 ? [a, b] : [b, a];
+  return createHash("sha256").update(Buffer.from([1])).update(first).update(second).digest();
+}